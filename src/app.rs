@@ -1,26 +1,48 @@
 use crate::block::Block;
+use crate::clipboard::Clipboard;
 use crate::data::Data;
+use crate::decode;
+use crate::disasm;
+use crate::disasm::Disassembler;
+use crate::elf::Elf;
 use crate::files::File;
 use crate::files::Files;
+use crate::files::WriteSummary;
+use crate::files::MAX_BLOCK_SIZE;
+use crate::files::MAX_PATCH_SIZE;
 use crate::hits::Hits;
+use crate::inspect;
+use crate::keys::Action;
+use crate::keys::KeyBindings;
+use crate::keys::ACTIONS;
+use crate::magic::sniff;
 use crate::modes::element_display_size;
 use crate::modes::element_mode_base;
+use crate::modes::AsmArch;
 use crate::modes::AsmDisplay;
+use crate::modes::ColorMode;
 use crate::modes::Display;
 use crate::modes::ElementDisplay;
 use crate::modes::ElementMode;
 use crate::modes::PrintDisplay;
+use crate::modes::PrintGutter;
 use crate::modes::VisualDisplay;
+use crate::pe::Pe;
 use crate::print::Print;
+use crate::recent;
+use crate::session::Session;
+use crate::tabs::Tab;
 use crate::tabs::Tabs;
+use crate::tabs::INSERT_VECTOR_SIZE;
+use crate::theme::density_glyph;
+use crate::theme::rgb_color;
 use crate::theme::Theme;
 use crossterm::event::KeyCode;
 use crossterm::event::KeyEvent;
 use crossterm::event::KeyModifiers;
 use memmem::{Searcher, TwoWaySearcher};
-use safe_transmute::base::from_bytes;
+use std::borrow::Cow;
 use std::collections::BTreeMap;
-use std::fs;
 use std::io;
 use std::io::prelude::*;
 use std::io::Cursor;
@@ -31,15 +53,14 @@ use std::result::Result;
 use std::time::Instant;
 
 use tui::{
-    style::{Color, Style},
+    style::Style,
     text::{Span, Spans},
 };
 
 use tui_textarea::TextArea;
 
 use iced_x86::{
-    Decoder, DecoderOptions, FormatterOutput, FormatterTextKind, GasFormatter, Instruction,
-    IntelFormatter, MasmFormatter, NasmFormatter,
+    FormatterTextKind, GasFormatter, IntelFormatter, MasmFormatter, NasmFormatter, NumberBase,
 };
 
 pub const DISPLAYS: &[Display] = &[
@@ -54,6 +75,7 @@ pub const ELEMENT_DISPLAYS: &[ElementDisplay] = &[
     ElementDisplay::Word,
     ElementDisplay::DWord,
     ElementDisplay::QWord,
+    ElementDisplay::OWord,
 ];
 
 pub const PRINT_DISPLAYS: &[PrintDisplay] = &[
@@ -70,8 +92,98 @@ pub const ELEMENT_MODES: &[ElementMode] = &[
     ElementMode::Dec,
     ElementMode::Oct,
     ElementMode::Bin,
+    ElementMode::Bit,
 ];
 
+pub const THEMES: &[&str] = &["default", "light", "high_contrast"];
+
+pub const COMMANDS: &[&str] = &[
+    "filetype",
+    "elf",
+    "pe",
+    "file",
+    "tab",
+    "search",
+    "block_size",
+    "offset",
+    "print",
+    "show",
+    "session",
+    "theme",
+    "wrap",
+    "hit",
+    "xor",
+    "add",
+    "sub",
+    "rol",
+    "ror",
+    "bswap",
+    "nop",
+    "asm",
+    "snap",
+    "step",
+    "align",
+    "watch",
+    "recent",
+    "rows",
+    "log",
+    "placeholder",
+    "relbase",
+    "inspect",
+    "decode",
+    "cursor",
+    "tabwidth",
+    "tail",
+    "cursors",
+    "carve",
+    "xref",
+    "changes",
+    "undolimit",
+    "bookmark",
+];
+
+const SEARCH_ENCODINGS: &[&str] = &["utf16", "utf16le", "utf16be"];
+
+pub const MAX_CURSORS: u64 = 65536;
+pub const DEFAULT_TAB_WIDTH: usize = 8;
+pub const MAX_TAB_WIDTH: u64 = 64;
+const MAX_REPEAT_COUNT: usize = 99999;
+const CHANGES_BLOCK: u64 = 65536;
+
+// actions a typed count prefix repeats, everything else runs once
+const REPEATABLE_ACTIONS: &[Action] = &[
+    Action::Up,
+    Action::Down,
+    Action::Left,
+    Action::Right,
+    Action::PageUp,
+    Action::PageDown,
+    Action::NextHit,
+    Action::PrevHit,
+    Action::NextHitGroup,
+    Action::PrevHitGroup,
+    Action::StepForward,
+    Action::StepBackward,
+];
+
+// named or single column characters shown for non-printable bytes
+const PLACEHOLDERS: &[(&str, char)] = &[
+    ("dot", '.'),
+    ("space", ' '),
+    ("underscore", '_'),
+    ("middot", '\u{b7}'),
+    ("bullet", '\u{2022}'),
+    ("shade", '\u{2591}'),
+];
+
+const VIEW_KINDS: &[&str] = &[
+    "byte", "word", "dword", "qword", "oword", "asm", "print", "visual",
+];
+const ELEMENT_MODE_NAMES: &[&str] = &["hex", "dec", "oct", "bin"];
+const SHOW_MODE_NAMES: &[&str] = &["hex", "dec", "oct", "bin", "bit"];
+const PRINT_MODE_NAMES: &[&str] = &["ascii", "ascii_escape", "unicode", "unicode_escape"];
+const VISUAL_MODE_NAMES: &[&str] = &["color", "entropy"];
+
 pub const ASM_DISPLAYS: &[AsmDisplay] = &[
     AsmDisplay::Nasm,
     AsmDisplay::Masm,
@@ -79,32 +191,65 @@ pub const ASM_DISPLAYS: &[AsmDisplay] = &[
     AsmDisplay::Intel,
 ];
 
-struct AsmFormatterOutput {
-    pub vec: Vec<(String, FormatterTextKind)>,
+const ASM_ARCH_NAMES: &[&str] = &["x86", "x86_64", "arm", "arm64"];
+
+const HEXBYTES_COLUMN_BYTE_LENGTH: usize = 16;
+// longest x86 instruction, bytes read past the block per screen row
+const ASM_LOOKAHEAD: u64 = 15;
+// bytes shown on each side of a hit when jumping to it
+const HIT_CONTEXT: u64 = 4;
+
+#[derive(Clone)]
+pub struct AsmLine {
+    pub offset: u64,
+    pub len: usize,
+    pub target: Option<u64>,
+    pub mem_target: Option<u64>,
+    pub invalid: bool,
 }
 
-impl AsmFormatterOutput {
-    pub fn new() -> Self {
-        Self { vec: Vec::new() }
-    }
+#[derive(Clone, Copy)]
+pub struct AsmOptions {
+    pub digit_separator: bool,
+    pub operand_column: u32,
+    pub uppercase_hex: bool,
+    pub number_base: NumberBase,
+    pub space_after_comma: bool,
 }
 
-impl FormatterOutput for AsmFormatterOutput {
-    fn write(&mut self, text: &str, kind: FormatterTextKind) {
-        self.vec.push((String::from(text), kind));
+impl AsmOptions {
+    pub fn default() -> AsmOptions {
+        AsmOptions {
+            digit_separator: true,
+            operand_column: 10,
+            uppercase_hex: true,
+            number_base: NumberBase::Hexadecimal,
+            space_after_comma: false,
+        }
     }
-}
 
-const HEXBYTES_COLUMN_BYTE_LENGTH: usize = 16;
+    fn apply<T: iced_x86::Formatter>(&self, formatter: &mut T) {
+        let options = formatter.options_mut();
+        options.set_digit_separator(if self.digit_separator { "`" } else { "" });
+        options.set_first_operand_char_index(self.operand_column);
+        options.set_uppercase_hex(self.uppercase_hex);
+        options.set_number_base(self.number_base);
+        options.set_space_after_operand_separator(self.space_after_comma);
+    }
+}
 
 #[derive(Clone)]
 pub struct Cache<'a> {
     pub buffer: Vec<Spans<'a>>,
+    pub asm: Vec<AsmLine>,
 }
 
 impl<'a> Cache<'a> {
     pub fn default() -> Cache<'a> {
-        Cache { buffer: Vec::new() }
+        Cache {
+            buffer: Vec::new(),
+            asm: Vec::new(),
+        }
     }
 }
 
@@ -114,6 +259,7 @@ pub struct App<'a> {
     pub should_quit: bool,
     pub enter_prompt: bool,
     pub show_history: bool,
+    pub show_hits: bool,
     pub show_help: bool,
     pub files: Files,
     pub tabs: Tabs,
@@ -122,10 +268,34 @@ pub struct App<'a> {
     pub textarea: TextArea<'a>,
     pub cache: Cache<'a>,
     pub theme: Theme,
+    pub theme_index: usize,
+    pub color_mode: ColorMode,
+    pub keys: KeyBindings,
     pub nasm_formatter: NasmFormatter,
     pub masm_formatter: MasmFormatter,
     pub gas_formatter: GasFormatter,
     pub intel_formatter: IntelFormatter,
+    pub asm_options: AsmOptions,
+    pub disassembler: Option<(AsmArch, Box<dyn Disassembler>)>,
+    pub synced_tab: Option<usize>,
+    pub verify_writes: bool,
+    pub placeholder: char,
+    pub tab_width: usize,
+    pub repeat_count: usize,
+    pub at: Option<String>,
+    pub last_command: Option<String>,
+    pub clipboard: Clipboard,
+}
+
+// offsets in gutters are shown relative to the tab's relbase, signed once it is set
+pub fn rel_offset(offset: u64, base: u64) -> String {
+    if base == 0 {
+        format!("0x{:08x}", offset)
+    } else if offset >= base {
+        format!(" 0x{:08x}", offset - base)
+    } else {
+        format!("-0x{:08x}", base - offset)
+    }
 }
 
 macro_rules! get_header {
@@ -139,74 +309,80 @@ macro_rules! get_header {
 }
 
 macro_rules! get_ascii {
-    ($z:ident, $theme:ident) => {
+    ($z:ident, $placeholder:ident, $ascii:expr, $noascii:expr) => {
         if $z.is_ascii_graphic() {
-            Span::styled(format!("{}", $z as char), $theme.ascii)
+            Span::styled(format!("{}", $z as char), $ascii)
         } else {
-            Span::styled(format!("{}", '.'), $theme.noascii)
+            Span::styled(format!("{}", $placeholder), $noascii)
         }
     };
 }
 
 macro_rules! get_values {
-    ($element_type:ty, $fmt:literal, $reader:ident, $ivector:ident, $pw:ident, $x:ident, $y:ident, $column:ident, $row:ident, $offset:ident, $buffer:ident, $theme:ident, $source:ident) => {
+    ($element_type:ty, $fmt:literal, $reader:ident, $pw:ident, $x:ident, $y:ident, $column:ident, $row:ident, $offset:ident, $buffer:ident, $theme:ident, $source:ident, $hits:ident, $cursor_style:ident, $classes:ident, $placeholder:ident, $rel_base:ident, $selection:ident, $bookmarks:ident) => {
         if $x == 0 {
             if $reader.position() >= $buffer.len() as u64 {
                 Span::styled(" ", $theme.null)
-            } else if $row == $y {
-                const ELEMENT_SIZE: usize = size_of::<$element_type>() as usize;
-                Span::styled(
-                    format!(
-                        "0x{:08x} ",
-                        $offset + (ELEMENT_SIZE * $pw * $y as usize) as u64
-                    ),
-                    $theme.current_offset,
-                )
             } else {
                 const ELEMENT_SIZE: usize = size_of::<$element_type>() as usize;
-                Span::styled(
-                    format!(
-                        "0x{:08x} ",
-                        $offset + (ELEMENT_SIZE * $pw * $y as usize) as u64
-                    ),
-                    $theme.offset,
-                )
+                let start = $offset + (ELEMENT_SIZE * $pw * $y as usize) as u64;
+                // a bookmark anywhere in the row marks its offset
+                let marked = $bookmarks
+                    .range(start..start + (ELEMENT_SIZE * $pw) as u64)
+                    .next()
+                    .is_some();
+                let style = if $row == $y {
+                    $theme.current_offset
+                } else if marked {
+                    $theme.bookmark
+                } else {
+                    $theme.offset
+                };
+                let mark = if marked { '>' } else { ' ' };
+                Span::styled(format!("{}{}", rel_offset(start, $rel_base), mark), style)
             }
         } else if $x == $pw + 1 {
             Span::styled("  ", $theme.null)
         } else if $x > $pw + 1 {
-            if $reader.position() >= $buffer.len() as u64 {
-                Span::styled(" ", $theme.null)
-            } else {
-                const ELEMENT_SIZE: usize = size_of::<$element_type>() as usize;
-                let idx = ELEMENT_SIZE * ($pw * $y as usize) + $x - $pw - 2;
-                if idx < $buffer.len() {
-                    let c = $buffer[idx];
-                    get_ascii!(c, $theme)
-                } else {
-                    Span::styled(" ", $theme.null)
+            // ascii gutter has one column per byte of the element grid of this row
+            const ELEMENT_SIZE: usize = size_of::<$element_type>() as usize;
+            let byte = $x - $pw - 2;
+            let column = usize::from($column);
+            match $buffer.get(ELEMENT_SIZE * $pw * $y as usize + byte) {
+                Some(&c) if $row == $y && byte >= column && byte < column + ELEMENT_SIZE => {
+                    get_ascii!(c, $placeholder, $cursor_style, $cursor_style)
                 }
+                Some(&c) => get_ascii!(c, $placeholder, $theme.ascii, $theme.noascii),
+                None => Span::styled(" ", $theme.null),
             }
         } else {
             if $reader.position() >= $buffer.len() as u64 {
-                Span::styled(" ", $theme.null)
+                // keep the ascii gutter of a short last row aligned
+                let width = format!($fmt, <$element_type>::default()).len();
+                Span::styled(" ".repeat(width), $theme.null)
             } else {
                 const ELEMENT_SIZE: usize = size_of::<$element_type>() as usize;
                 let mut ovector: [u8; ELEMENT_SIZE] = [0; ELEMENT_SIZE];
                 let mut vector: [u8; ELEMENT_SIZE] = [0; ELEMENT_SIZE];
+                let pos = $offset + $reader.position();
                 let _read = $reader.read(&mut vector);
-                let original;
-                let val;
-                unsafe {
-                    val = from_bytes::<$element_type>(&vector);
-                }
+                // byte arrays are not aligned for the wider types, so no transmute here
+                let val = <$element_type>::from_le_bytes(vector);
                 let _oread = $source.read(&mut ovector);
-                unsafe {
-                    original = from_bytes::<$element_type>(&ovector);
-                }
+                let original = <$element_type>::from_le_bytes(ovector);
                 let style;
                 if val != original {
                     style = $theme.edited;
+                } else if $selection.map_or(false, |(start, end)| {
+                    pos < end && pos + ELEMENT_SIZE as u64 > start
+                }) {
+                    style = $theme.selection;
+                } else if $hits.map_or(false, |h| h.contains(pos, pos + ELEMENT_SIZE as u64)) {
+                    style = $theme.hit;
+                } else if $classes && vector.iter().all(|&b| b == 0) {
+                    style = $theme.zero;
+                } else if $classes && ELEMENT_SIZE == 1 && (0x20..0x7f).contains(&vector[0]) {
+                    style = $theme.ascii;
                 } else {
                     style = $theme.text;
                 }
@@ -214,17 +390,14 @@ macro_rules! get_values {
                     && usize::from($column) >= ($x - 1) * ELEMENT_SIZE
                     && usize::from($column) < ($x) * ELEMENT_SIZE
                 {
-                    let number = val.unwrap();
-                    let zz = format!($fmt, number);
-                    let letters: Vec<u8> = zz.trim().as_bytes().to_vec();
-                    *$ivector = Self::pop(&letters);
+                    let zz = format!($fmt, val);
                     if usize::from($column) == ($x - 1) * ELEMENT_SIZE {
-                        Span::styled(zz, $theme.current_text)
+                        Span::styled(zz, $cursor_style)
                     } else {
                         Span::styled(zz, style)
                     }
                 } else {
-                    Span::styled(format!($fmt, val.unwrap()), style)
+                    Span::styled(format!($fmt, val), style)
                 }
             }
         }
@@ -233,6 +406,7 @@ macro_rules! get_values {
 
 macro_rules! get_element {
     ($element_type:ty, $app:ident, $fmt:literal, $hdr_fmt:literal) => {
+        let selection = $app.selection();
         let cache = &mut $app.cache;
         let theme = $app.theme;
         let fi = $app.files.current($app.tabs.file_index());
@@ -241,12 +415,24 @@ macro_rules! get_element {
         let print_width = std::cmp::max((ti.print_width + (ELEMENT_SIZE - 1)) / ELEMENT_SIZE, 1);
         let print_height = ti.print_height;
         let mut row = ti.cursor_row;
+        let squeeze = ti.squeeze && !ti.insert_mode;
+        let row_size = ELEMENT_SIZE * print_width;
+        let mut squeezed = false;
         let column = ti.cursor_column & !((ELEMENT_SIZE - 1) as u16);
-        let ivector = &mut ti.insert_vector;
-        let buffer = &fi.block.buffer;
+        let buffer = fi.block.data();
         let mut source = Cursor::new(&fi.block.source);
         let mut reader = Cursor::new(&fi.block.buffer);
         let offset = fi.block.offset;
+        let hits = fi.hhits.hits.get(fi.hhits.selected);
+        let bookmarks = &fi.bookmarks;
+        let classes = ti.byte_classes;
+        let placeholder = $app.placeholder;
+        let rel_base = ti.rel_base;
+        let cursor_style = if ti.insert_error {
+            theme.error
+        } else {
+            theme.current_text
+        };
 
         if !ti.insert_mode {
             row = print_height + 1;
@@ -260,15 +446,32 @@ macro_rules! get_element {
                 .collect::<Vec<Span>>(),
         ));
 
+        if ti.ruler {
+            let width = format!($fmt, <$element_type>::default()).len();
+            let mut guide = vec![Span::styled(" ".repeat(11), theme.ruler)];
+            for x in 0..print_width {
+                let mark = match (x * ELEMENT_SIZE) % 16 {
+                    0 => '|',
+                    8 => '+',
+                    4 | 12 => ':',
+                    _ => '.',
+                };
+                guide.push(Span::styled(
+                    format!(" {}{}", mark, " ".repeat(width - 2)),
+                    theme.ruler,
+                ));
+            }
+            cache.buffer.push(tui::text::Spans(guide));
+        }
+
         for y in 0..print_height {
-            cache.buffer.push(tui::text::Spans(
+            let line = tui::text::Spans(
                 (0..(print_width + print_width * ELEMENT_SIZE) + 2)
                     .map(|x| {
                         get_values!(
                             $element_type,
                             $fmt,
                             reader,
-                            ivector,
                             print_width,
                             x,
                             y,
@@ -277,21 +480,44 @@ macro_rules! get_element {
                             offset,
                             buffer,
                             theme,
-                            source
+                            source,
+                            hits,
+                            cursor_style,
+                            classes,
+                            placeholder,
+                            rel_base,
+                            selection,
+                            bookmarks
                         )
                     })
                     .collect::<Vec<Span>>(),
-            ));
+            );
+            if squeeze && y > 0 && y + 1 < print_height {
+                let start = row_size * y as usize;
+                let current = buffer.get(start..start + row_size);
+                if current.is_some() && current == buffer.get(start - row_size..start) {
+                    if !squeezed {
+                        cache
+                            .buffer
+                            .push(tui::text::Spans::from(Span::styled("*", theme.offset)));
+                        squeezed = true;
+                    }
+                    continue;
+                }
+            }
+            squeezed = false;
+            cache.buffer.push(line);
         }
     };
 }
 
 macro_rules! flush_input_item {
     ($element_type:ty, $size:ident, $input:ident, $base:ident, $vv:ident, $r:ident) => {
-        let s = String::from_utf8($input.get(0..$size as usize).unwrap().to_vec()).unwrap();
-        let v = <$element_type>::from_str_radix(&s, $base);
-        if v.is_ok() {
-            *$vv = v.unwrap().to_le_bytes().to_vec();
+        let s = $input
+            .get(0..$size as usize)
+            .and_then(|digits| std::str::from_utf8(digits).ok());
+        if let Some(Ok(v)) = s.map(|s| <$element_type>::from_str_radix(s, $base)) {
+            *$vv = v.to_le_bytes().to_vec();
             $r = true;
         }
     };
@@ -299,12 +525,13 @@ macro_rules! flush_input_item {
 
 impl<'a> App<'a> {
     pub fn new(title: &'a str, paths: Vec<String>) -> App<'a> {
-        App {
+        let mut app = App {
             title,
             paths,
             should_quit: false,
             enter_prompt: false,
             show_history: false,
+            show_hits: false,
             show_help: false,
             progress: 0.0,
             now: Instant::now(),
@@ -313,23 +540,263 @@ impl<'a> App<'a> {
             files: Files::default(),
             tabs: Tabs::default(),
             theme: Theme::default(),
+            theme_index: 0,
+            color_mode: ColorMode::TrueColor,
+            keys: KeyBindings::default(),
             nasm_formatter: NasmFormatter::new(),
             masm_formatter: MasmFormatter::new(),
             gas_formatter: GasFormatter::new(),
             intel_formatter: IntelFormatter::new(),
+            asm_options: AsmOptions::default(),
+            disassembler: None,
+            synced_tab: None,
+            verify_writes: false,
+            placeholder: '.',
+            tab_width: DEFAULT_TAB_WIDTH,
+            repeat_count: 0,
+            at: None,
+            last_command: None,
+            clipboard: Clipboard::default(),
+        };
+        app.apply_asm_options();
+        app
+    }
+
+    fn apply_asm_options(&mut self) {
+        self.asm_options.apply(&mut self.nasm_formatter);
+        self.asm_options.apply(&mut self.masm_formatter);
+        self.asm_options.apply(&mut self.gas_formatter);
+        self.asm_options.apply(&mut self.intel_formatter);
+    }
+
+    fn set_asm_option(&mut self, print: &mut Print, name: &str, value: &str) {
+        let on = value.eq("on");
+        if ["separator", "uppercase", "space"].contains(&name) && !on && !value.eq("off") {
+            self.check_keyword(print, "value", value, &["on", "off"]);
+            return;
+        }
+        if name.eq("separator") {
+            self.asm_options.digit_separator = on;
+        } else if name.eq("uppercase") {
+            self.asm_options.uppercase_hex = on;
+        } else if name.eq("space") {
+            self.asm_options.space_after_comma = on;
+        } else if name.eq("operand") {
+            match self.parse_number_arg(print, "operand column", value) {
+                Some(column) => self.asm_options.operand_column = column as u32,
+                None => return,
+            }
+        } else if name.eq("arch") {
+            self.set_asm_arch(print, value);
+            return;
+        } else if name.eq("base") {
+            self.asm_options.number_base = match value {
+                "2" => NumberBase::Binary,
+                "8" => NumberBase::Octal,
+                "10" => NumberBase::Decimal,
+                "16" => NumberBase::Hexadecimal,
+                _ => {
+                    self.check_keyword(print, "number base", value, &["2", "8", "10", "16"]);
+                    return;
+                }
+            };
+        } else {
+            self.check_keyword(
+                print,
+                "asm option",
+                name,
+                &[
+                    "stats",
+                    "arch",
+                    "separator",
+                    "operand",
+                    "uppercase",
+                    "base",
+                    "space",
+                ],
+            );
+            return;
         }
+        self.apply_asm_options();
     }
 
-    fn handle_search(&mut self, item: String) -> io::Result<usize> {
-        let path = &self.files.current_path(&mut self.tabs);
-        let mut file = std::fs::File::open(path)?;
-        let len = fs::metadata(path)?.len();
-        let mut block = Block::new(2048usize);
-        let mut offset = 0u64;
-        let search_bytes = item.as_str().as_bytes();
+    fn set_asm_arch(&mut self, print: &mut Print, value: &str) {
+        if !self.check_keyword(print, "asm arch", value, ASM_ARCH_NAMES) {
+            return;
+        }
+        let arch = match value {
+            "x86" => AsmArch::X86,
+            "x86_64" => AsmArch::X86_64,
+            "arm" => AsmArch::Arm,
+            _ => AsmArch::Arm64,
+        };
+        if ![AsmArch::X86, AsmArch::X86_64].contains(&arch) {
+            match disasm::other(arch) {
+                Ok(disassembler) => self.disassembler = Some((arch, disassembler)),
+                Err(e) => {
+                    print.history.print(self.theme.error, e);
+                    return;
+                }
+            }
+        }
+        if !self.tabs.tabs.is_empty() {
+            self.tabs.current().asm_arch = arch;
+        }
+    }
+
+    fn parse_pattern(input: &str) -> io::Result<Vec<u8>> {
+        let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidInput, msg);
+        let quoted = input.len() > 1 && input.starts_with('"') && input.ends_with('"');
+        if !quoted {
+            if input.is_empty() {
+                return Err(invalid("empty pattern".to_string()));
+            }
+            return Ok(input.as_bytes().to_vec());
+        }
+        let mut bytes = Vec::new();
+        let mut chars = input[1..input.len() - 1].chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                let mut utf8 = [0u8; 4];
+                bytes.extend_from_slice(c.encode_utf8(&mut utf8).as_bytes());
+                continue;
+            }
+            match chars.next() {
+                Some('x') => {
+                    let hex: String = chars.by_ref().take(2).collect();
+                    let byte = if hex.len() == 2 {
+                        u8::from_str_radix(&hex, 16).ok()
+                    } else {
+                        None
+                    };
+                    match byte {
+                        Some(byte) => bytes.push(byte),
+                        None => return Err(invalid(format!("malformed escape \\x{}", hex))),
+                    }
+                }
+                Some('0') => bytes.push(0),
+                Some('n') => bytes.push(b'\n'),
+                Some('r') => bytes.push(b'\r'),
+                Some('t') => bytes.push(b'\t'),
+                Some('\\') => bytes.push(b'\\'),
+                Some('"') => bytes.push(b'"'),
+                Some(c) => return Err(invalid(format!("unknown escape \\{}", c))),
+                None => return Err(invalid("trailing backslash".to_string())),
+            }
+        }
+        if bytes.is_empty() {
+            return Err(invalid("empty pattern".to_string()));
+        }
+        Ok(bytes)
+    }
+
+    // value <n> <width> <le|be> searches for n stored as an integer of that many bytes
+    fn value_bytes(args: &str) -> io::Result<Vec<u8>> {
+        let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidInput, msg);
+        let args: Vec<&str> = args.split_whitespace().collect();
+        if args.len() != 3 {
+            return Err(invalid("expected value <n> <width> <le|be>".to_string()));
+        }
+        let value = Self::parse_u64_number(args[0])
+            .map_err(|e| invalid(format!("invalid value: {}: {}", args[0], e)))?;
+        let width = match args[1] {
+            "1" => 1,
+            "2" => 2,
+            "4" => 4,
+            "8" => 8,
+            _ => {
+                return Err(invalid(format!(
+                    "invalid width: {}: expected 1, 2, 4 or 8",
+                    args[1]
+                )))
+            }
+        };
+        if width < 8 && value >> (width * 8) != 0 {
+            return Err(invalid(format!(
+                "value 0x{:x} does not fit in {} bytes",
+                value, width
+            )));
+        }
+        match args[2] {
+            "le" => Ok(value.to_le_bytes()[..width].to_vec()),
+            "be" => Ok(value.to_be_bytes()[8 - width..].to_vec()),
+            _ => Err(invalid(format!(
+                "invalid endianness: {}: expected le or be",
+                args[2]
+            ))),
+        }
+    }
+
+    // a leading encoding name searches for the text as UTF-16 code units instead of UTF-8
+    fn search_bytes(item: &str) -> io::Result<Vec<u8>> {
+        if let Some(args) = item.strip_prefix("value ") {
+            return Self::value_bytes(args);
+        }
+        let (encoding, text) = match item.split_once(' ') {
+            Some((encoding, text)) if SEARCH_ENCODINGS.contains(&encoding) => {
+                (encoding, text.trim_start())
+            }
+            _ => return Self::parse_pattern(item),
+        };
+        let text = String::from_utf8(Self::parse_pattern(text)?).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} text must be valid UTF-8", encoding),
+            )
+        })?;
+        Ok(text
+            .encode_utf16()
+            .flat_map(|unit| {
+                if encoding.eq("utf16be") {
+                    unit.to_be_bytes()
+                } else {
+                    unit.to_le_bytes()
+                }
+            })
+            .collect())
+    }
+
+    // strips trailing "from <offset>" and "to <offset>" bounds off a search query
+    fn search_range(item: &str) -> io::Result<(&str, u64, u64)> {
+        let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidInput, msg);
+        let mut query = item;
+        let mut from = 0u64;
+        let mut to = u64::MAX;
+        for keyword in ["to", "from"] {
+            let mut words = query.rsplitn(3, char::is_whitespace);
+            let (value, name, rest) = (words.next(), words.next(), words.next());
+            if let (Some(value), Some(name), Some(rest)) = (value, name, rest) {
+                if name.eq(keyword) {
+                    let bound = Self::parse_u64_number(value).map_err(|e| {
+                        invalid(format!("invalid {} offset: {}: {}", keyword, value, e))
+                    })?;
+                    if keyword.eq("to") {
+                        to = bound;
+                    } else {
+                        from = bound;
+                    }
+                    query = rest.trim_end();
+                }
+            }
+        }
+        if from >= to {
+            return Err(invalid("from offset must be below to offset".to_string()));
+        }
+        Ok((query, from, to))
+    }
+
+    fn scan_hits(&mut self, item: String) -> io::Result<Hits> {
+        let fi = self.files.current(self.tabs.file_index());
+        let (mut file, len) = fi.source.reader(&fi.path)?;
+        let (query, from, to) = Self::search_range(&item)?;
+        let len = std::cmp::min(len, to);
+        let mut offset = from;
+        let search_bytes = Self::search_bytes(query)?;
         let search_len = search_bytes.len() as u64;
-        let search = TwoWaySearcher::new(search_bytes);
-        let mut hits = Hits::new(item.clone());
+        // each window holds block.size starting positions plus the overlap into the next one
+        let mut block = Block::new(std::cmp::max(2048usize, search_bytes.len()));
+        let search = TwoWaySearcher::new(&search_bytes);
+        let mut hits = Hits::new(item.clone(), search_len);
 
         while offset < len {
             block.offset = offset;
@@ -340,84 +807,196 @@ impl<'a> App<'a> {
                 len,
                 &mut block.buffer,
             )?;
-            let r = search.search_in(&block.buffer);
-            if r.is_some() {
-                let hit_offset = offset + r.unwrap() as u64;
+            let mut start = 0usize;
+            while start < block.size as usize {
+                let r = search.search_in(&block.buffer[start..]);
+                if r.is_none() {
+                    break;
+                }
+                let pos = start + r.unwrap();
+                let hit_offset = offset + pos as u64;
+                if pos >= block.size as usize || hit_offset + search_len > len {
+                    break;
+                }
                 hits.hits.push(hit_offset);
+                start = pos + 1;
             }
             offset += block.size;
         }
+        Ok(hits)
+    }
+
+    fn handle_search(&mut self, item: String) -> io::Result<usize> {
+        let hits = self.scan_hits(item)?;
         let fi = &mut self.files.current(self.tabs.file_index());
         let found_items = hits.hits.len();
         fi.hhits.add(hits);
         Ok(found_items)
     }
 
-    fn handle_print(&mut self, print: &mut Print<'a>, kind: String, mode: String) {
-        if !self.files.files.is_empty() {
-            if kind.eq("byte") {
-                if mode.eq("hex") {
-                    print.hexbyte(self);
-                } else if mode.eq("dec") {
-                    print.decbyte(self);
-                } else if mode.eq("oct") {
-                    print.octbyte(self);
-                } else if mode.eq("bin") {
-                    print.binbyte(self);
-                }
-            } else if kind.eq("word") {
-                if mode.eq("hex") {
-                    print.hexword(self);
-                } else if mode.eq("dec") {
-                    print.decword(self);
-                } else if mode.eq("oct") {
-                    print.octword(self);
-                } else if mode.eq("bin") {
-                    print.binword(self);
-                }
-            } else if kind.eq("dword") {
-                if mode.eq("hex") {
-                    print.hexdword(self);
-                } else if mode.eq("dec") {
-                    print.decdword(self);
-                } else if mode.eq("oct") {
-                    print.octdword(self);
-                } else if mode.eq("bin") {
-                    print.bindword(self);
-                }
-            } else if kind.eq("qword") {
-                if mode.eq("hex") {
-                    print.hexqword(self);
-                } else if mode.eq("dec") {
-                    print.decqword(self);
-                } else if mode.eq("oct") {
-                    print.octqword(self);
-                } else if mode.eq("bin") {
-                    print.binqword(self);
-                }
-            } else if kind.eq("asm") {
-                print.asm(self);
-            } else if kind.eq("print") {
-                if mode.eq("ascii") {
-                    print.ascii_print(self);
-                } else if mode.eq("ascii_escape") {
-                    print.ascii_escape(self);
-                } else if mode.eq("unicode") {
-                    print.unicode_print(self);
-                } else if mode.eq("unicode_escape") {
-                    print.unicode_escape(self);
-                }
-            } else if kind.eq("visual") {
-                if mode.eq("color") {
-                    print.color(self);
-                } else if mode.eq("entropy") {
-                    print.entropy(self);
-                }
+    fn check_keyword(&self, print: &mut Print, what: &str, value: &str, valid: &[&str]) -> bool {
+        if valid.contains(&value) {
+            true
+        } else {
+            print.history.print(
+                self.theme.error,
+                format!(
+                    "unknown {}: {} (expected one of: {})",
+                    what,
+                    value,
+                    valid.join(", ")
+                ),
+            );
+            false
+        }
+    }
+
+    fn view_modes(kind: &str) -> &'static [&'static str] {
+        match kind {
+            "byte" | "word" | "dword" | "qword" | "oword" => ELEMENT_MODE_NAMES,
+            "print" => PRINT_MODE_NAMES,
+            "visual" => VISUAL_MODE_NAMES,
+            _ => &[],
+        }
+    }
+
+    fn handle_print(
+        &mut self,
+        print: &mut Print<'a>,
+        kind: String,
+        mode: String,
+        rows: Option<&str>,
+    ) {
+        if !self.check_keyword(print, "print kind", &kind, VIEW_KINDS) {
+            return;
+        }
+        let modes = Self::view_modes(&kind);
+        if !modes.is_empty() && !self.check_keyword(print, "print mode", &mode, modes) {
+            return;
+        }
+        if self.files.files.is_empty() {
+            return;
+        }
+        match rows {
+            Some(input) => self.print_rows(print, &kind, &mode, input),
+            None => self.print_view(print, &kind, &mode),
+        }
+    }
+
+    fn print_rows(&mut self, print: &mut Print<'a>, kind: &str, mode: &str, input: &str) {
+        let rows = match self.parse_number_arg(print, "rows", input) {
+            Some(rows) => rows.clamp(1, u16::MAX as u64) as u16,
+            None => return,
+        };
+        let element_size = match kind {
+            "word" => 2,
+            "dword" => 4,
+            "qword" => 8,
+            "oword" => 16,
+            _ => 1,
+        };
+        let pw = self.tabs.current().print_width;
+        let row_size = element_size * std::cmp::max((pw + element_size - 1) / element_size, 1);
+        let size = std::cmp::min(rows as u64 * row_size as u64, MAX_BLOCK_SIZE);
+        let index = self.tabs.file_index();
+        let offset = self.current_offset();
+        let block = match self.files.read_block_at(index, offset, size) {
+            Ok(block) => block,
+            Err(e) => {
+                print
+                    .history
+                    .print(self.theme.error, format!("Print failed: {}", e));
+                return;
+            }
+        };
+        let saved_block = std::mem::replace(&mut self.files.current(index).block, block);
+        let saved_height = std::mem::replace(&mut self.tabs.current().print_height, rows);
+        self.print_view(print, kind, mode);
+        self.files.current(index).block = saved_block;
+        self.tabs.current().print_height = saved_height;
+    }
+
+    fn print_view(&mut self, print: &mut Print<'a>, kind: &str, mode: &str) {
+        if kind.eq("byte") {
+            if mode.eq("hex") {
+                print.hexbyte(self);
+            } else if mode.eq("dec") {
+                print.decbyte(self);
+            } else if mode.eq("oct") {
+                print.octbyte(self);
+            } else if mode.eq("bin") {
+                print.binbyte(self);
+            }
+        } else if kind.eq("word") {
+            if mode.eq("hex") {
+                print.hexword(self);
+            } else if mode.eq("dec") {
+                print.decword(self);
+            } else if mode.eq("oct") {
+                print.octword(self);
+            } else if mode.eq("bin") {
+                print.binword(self);
+            }
+        } else if kind.eq("dword") {
+            if mode.eq("hex") {
+                print.hexdword(self);
+            } else if mode.eq("dec") {
+                print.decdword(self);
+            } else if mode.eq("oct") {
+                print.octdword(self);
+            } else if mode.eq("bin") {
+                print.bindword(self);
+            }
+        } else if kind.eq("qword") {
+            if mode.eq("hex") {
+                print.hexqword(self);
+            } else if mode.eq("dec") {
+                print.decqword(self);
+            } else if mode.eq("oct") {
+                print.octqword(self);
+            } else if mode.eq("bin") {
+                print.binqword(self);
+            }
+        } else if kind.eq("oword") {
+            if mode.eq("hex") {
+                print.hexoword(self);
+            } else if mode.eq("dec") {
+                print.decoword(self);
+            } else if mode.eq("oct") {
+                print.octoword(self);
+            } else if mode.eq("bin") {
+                print.binoword(self);
+            }
+        } else if kind.eq("asm") {
+            print.asm(self);
+        } else if kind.eq("print") {
+            if mode.eq("ascii") {
+                print.ascii_print(self);
+            } else if mode.eq("ascii_escape") {
+                print.ascii_escape(self);
+            } else if mode.eq("unicode") {
+                print.unicode_print(self);
+            } else if mode.eq("unicode_escape") {
+                print.unicode_escape(self);
+            }
+        } else if kind.eq("visual") {
+            if mode.eq("color") {
+                print.color(self);
+            } else if mode.eq("entropy") {
+                print.entropy(self);
             }
         }
     }
 
-    fn handle_show(&mut self, kind: String, mode: String) {
+    fn handle_show(&mut self, print: &mut Print, kind: String, mode: String) {
+        if !self.check_keyword(print, "show kind", &kind, VIEW_KINDS) {
+            return;
+        }
+        if Self::view_modes(&kind) == ELEMENT_MODE_NAMES
+            && !self.check_keyword(print, "show mode", &mode, SHOW_MODE_NAMES)
+        {
+            return;
+        }
         if !self.files.files.is_empty() {
             if kind.eq("byte") {
                 self.tabs.current().display = Display::Element;
@@ -435,6 +1014,10 @@ impl<'a> App<'a> {
                 self.tabs.current().display = Display::Element;
                 self.tabs.current().element_display = ElementDisplay::QWord;
                 self.tabs.element_mode(mode);
+            } else if kind.eq("oword") {
+                self.tabs.current().display = Display::Element;
+                self.tabs.current().element_display = ElementDisplay::OWord;
+                self.tabs.element_mode(mode);
             } else if kind.eq("asm") {
                 self.tabs.current().display = Display::Asm;
             } else if kind.eq("print") {
@@ -467,25 +1050,38 @@ impl<'a> App<'a> {
 
     pub fn get_color(&mut self) -> &Vec<Spans<'a>> {
         let offset_style = self.theme.offset;
+        let text_style = self.theme.text;
+        let color_mode = self.color_mode;
+        let placeholder = self.placeholder;
         let print_width = self.tabs.current().print_width;
+        let rel_base = self.tabs.current().rel_base;
         let fi = self.files.current(self.tabs.file_index());
         let buffer = &mut self.cache.buffer;
         let mut line = Vec::new();
-        let hex_iter = fi.block.buffer.iter();
+        let hex_iter = fi.block.data().iter();
         let mut offset = fi.block.offset;
         let mut i = 0;
 
         buffer.clear();
         for val in hex_iter {
-            let hex_val = String::from("__");
-            let red = (*val as u8).rotate_left(4);
-            let blue = (*val as u8).rotate_right(2);
-            let green = *val as u8;
-            let hex_color = Style::default()
-                .fg(Color::Rgb(red, green, blue))
-                .bg(Color::Rgb(red, green, blue));
+            let hex_val;
+            let hex_color;
+            if color_mode == ColorMode::Mono {
+                hex_val = density_glyph(*val).repeat(2);
+                hex_color = text_style;
+            } else {
+                let red = (*val as u8).rotate_left(4);
+                let blue = (*val as u8).rotate_right(2);
+                let green = *val as u8;
+                let color = rgb_color(color_mode, red, green, blue);
+                hex_val = placeholder.to_string().repeat(2);
+                hex_color = Style::default().fg(color).bg(color);
+            }
             if i == 0 {
-                line.push(Span::styled(format!("0x{:08x} ", offset), offset_style));
+                line.push(Span::styled(
+                    format!("{} ", rel_offset(offset, rel_base)),
+                    offset_style,
+                ));
             }
             line.push(Span::styled(hex_val, hex_color));
 
@@ -502,14 +1098,17 @@ impl<'a> App<'a> {
         &self.cache.buffer
     }
 
-    fn calc_entropy(block: &Block) -> f64 {
+    fn calc_entropy(block: &Block, nb_read: usize) -> f64 {
         let mut histogram = [0u64; 256];
-        let hex_iter = block.buffer.iter();
+        let hex_iter = block.buffer[..nb_read].iter();
         for val in hex_iter {
             histogram[*val as usize] += 1u64;
         }
+        if nb_read == 0 {
+            return 0.0;
+        }
         let mut entropy: f64 = 0.0;
-        let scale: f64 = 1.0f64 / (block.size as f64);
+        let scale: f64 = 1.0f64 / (nb_read as f64);
         for i in 0..256 {
             if histogram[i] > 0u64 {
                 let p: f64 = histogram[i] as f64 * scale;
@@ -520,12 +1119,18 @@ impl<'a> App<'a> {
     }
 
     pub fn get_entropy(&mut self) -> &Vec<Spans<'a>> {
-        let path = &self.files.current_path(&mut self.tabs);
-        let mut file = std::fs::File::open(path).unwrap();
-        let len = fs::metadata(path).expect("bug").len();
         let print_width = self.tabs.current().print_width;
         let print_height = self.tabs.current().print_height;
+        let text_style = self.theme.text;
+        let color_mode = self.color_mode;
         let fi = self.files.current(self.tabs.file_index());
+        let (mut file, len) = match fi.source.reader(&fi.path) {
+            Ok(reader) => reader,
+            Err(_) => {
+                self.cache.buffer.clear();
+                return &self.cache.buffer;
+            }
+        };
         let buffer = &mut self.cache.buffer;
         let mut offset = fi.block.offset;
         let mut block = Block::new(2048usize);
@@ -540,19 +1145,26 @@ impl<'a> App<'a> {
             if r.is_err() {
                 break;
             }
-            let entropy = Self::calc_entropy(&block);
+            let entropy = Self::calc_entropy(&block, r.unwrap());
             let scaled = ((255.0f64 * entropy).round()) as u8;
             let width = (entropy * (print_width as f64)).round() as u64;
-            let red = scaled.rotate_left(4);
-            let blue = scaled.rotate_right(2);
-            let green = scaled;
-            let hex_color = Style::default()
-                .fg(Color::Rgb(red, green, blue))
-                .bg(Color::Rgb(red, green, blue));
+            let glyph;
+            let hex_color;
+            if color_mode == ColorMode::Mono {
+                glyph = density_glyph(scaled);
+                hex_color = text_style;
+            } else {
+                let red = scaled.rotate_left(4);
+                let blue = scaled.rotate_right(2);
+                let green = scaled;
+                let color = rgb_color(color_mode, red, green, blue);
+                glyph = "_";
+                hex_color = Style::default().fg(color).bg(color);
+            }
 
             buffer.push(tui::text::Spans(
                 (0..width)
-                    .map(|_x| Span::styled("_", hex_color))
+                    .map(|_x| Span::styled(glyph, hex_color))
                     .collect::<Vec<Span>>(),
             ));
             offset += block.size;
@@ -620,77 +1232,186 @@ impl<'a> App<'a> {
         &self.cache.buffer
     }
 
-    fn get_asm_fmt<T: iced_x86::Formatter>(
+    pub fn get_decoword(&mut self) -> &Vec<Spans<'a>> {
+        get_element!(u128, self, " {:^039}", " {:^39x}");
+        &self.cache.buffer
+    }
+
+    pub fn get_octoword(&mut self) -> &Vec<Spans<'a>> {
+        get_element!(u128, self, " {:043o}", " {:^43x}");
+        &self.cache.buffer
+    }
+
+    pub fn get_binoword(&mut self) -> &Vec<Spans<'a>> {
+        get_element!(u128, self, " {:0128b}", " {:^128x}");
+        &self.cache.buffer
+    }
+
+    pub fn get_hexoword(&mut self) -> &Vec<Spans<'a>> {
+        get_element!(u128, self, " {:032x}", " {:^32x}");
+        &self.cache.buffer
+    }
+
+    fn get_asm_fmt(
         fi: &File,
         cache: &mut Cache,
         theme: Theme,
-        formatter: &mut T,
+        disassembler: &mut dyn Disassembler,
+        ti: &Tab,
+        lookahead: &[u8],
     ) {
         let mut theme = theme;
         let buffer = &mut cache.buffer;
         let mut line = Vec::new();
-        let current_offset = fi.block.offset;
-        let bytes = &fi.block.buffer;
-        let mut decoder = Decoder::with_ip(64, bytes, current_offset, DecoderOptions::NONE);
+        let current_offset = fi.image_base + fi.block.offset;
+        // padding past the end of file would decode into phantom instructions
+        let data = fi.block.data();
+        let block_end = current_offset + data.len() as u64;
+        let bytes: Cow<[u8]> = if lookahead.is_empty() {
+            Cow::Borrowed(data)
+        } else {
+            Cow::Owned([data, lookahead].concat())
+        };
+
+        let cursor_row = if ti.insert_mode {
+            Some(ti.cursor_row as usize)
+        } else {
+            None
+        };
 
         buffer.clear();
+        cache.asm.clear();
 
-        // Change some options, there are many more
-        formatter.options_mut().set_digit_separator("`");
-        formatter.options_mut().set_first_operand_char_index(10);
-
-        // Initialize this outside the loop because decode_out() writes to every field
-        let mut instruction = Instruction::default();
-
-        // The decoder also implements Iterator/IntoIterator so you could use a for loop:
-        //      for instruction in &mut decoder { /* ... */ }
-        // or collect():
-        //      let instructions: Vec<_> = decoder.into_iter().collect();
-        // but can_decode()/decode_out() is a little faster:
-        while decoder.can_decode() {
-            // There's also a decode() method that returns an instruction but that also
-            // means it copies an instruction (40 bytes):
-            //     instruction = decoder.decode();
-            decoder.decode_out(&mut instruction);
-
-            line.push(Span::styled(
-                format!("{:016X} ", instruction.ip()),
-                theme.offset,
-            ));
-            let start_index = (instruction.ip() - current_offset) as usize;
-            let instr_bytes = &bytes[start_index..start_index + instruction.len()];
-            for b in instr_bytes.iter() {
-                line.push(Span::styled(format!("{:02X}", b), theme.header));
+        for instruction in disassembler.disassemble(&bytes, current_offset) {
+            // bytes past the block only complete its last instruction and fill the screen
+            if instruction.ip >= block_end && buffer.len() >= ti.print_height as usize {
+                break;
             }
-            if instr_bytes.len() < HEXBYTES_COLUMN_BYTE_LENGTH {
-                for _ in 0..HEXBYTES_COLUMN_BYTE_LENGTH - instr_bytes.len() {
-                    line.push(Span::styled("  ", theme.text));
+            let target = instruction
+                .target
+                .filter(|t| *t >= fi.image_base)
+                .map(|t| t - fi.image_base);
+            let mem_target = instruction
+                .mem_target
+                .filter(|t| *t >= fi.image_base)
+                .map(|t| t - fi.image_base);
+            let offset = fi.block.offset + (instruction.ip - current_offset);
+            cache.asm.push(AsmLine {
+                offset,
+                len: instruction.len,
+                target,
+                mem_target,
+                invalid: instruction.invalid,
+            });
+            let offset_style = if cursor_row == Some(buffer.len()) {
+                theme.current_offset
+            } else {
+                theme.offset
+            };
+            let gutter = if ti.rel_base == 0 {
+                format!("{:016X} ", instruction.ip)
+            } else {
+                format!("{:>16} ", rel_offset(offset, ti.rel_base))
+            };
+            line.push(Span::styled(gutter, offset_style));
+            if ti.show_asm_bytes {
+                let start_index = (instruction.ip - current_offset) as usize;
+                let instr_bytes = &bytes[start_index..start_index + instruction.len];
+                for b in instr_bytes.iter() {
+                    line.push(Span::styled(format!("{:02X}", b), theme.header));
+                }
+                if instr_bytes.len() < HEXBYTES_COLUMN_BYTE_LENGTH {
+                    for _ in 0..HEXBYTES_COLUMN_BYTE_LENGTH - instr_bytes.len() {
+                        line.push(Span::styled("  ", theme.text));
+                    }
                 }
             }
-            let mut output = AsmFormatterOutput::new();
-            formatter.format(&instruction, &mut output);
-            for (text, kind) in output.vec.iter() {
+            for (text, kind) in instruction.text.iter() {
                 line.push(Span::styled(
                     text.clone(),
                     Self::get_asm_color(*kind, &mut theme),
                 ));
             }
+            if let Some(mem_target) = mem_target {
+                line.push(Span::styled(
+                    format!("  ; 0x{:x}", mem_target),
+                    theme.labeladdress,
+                ));
+            }
+            if instruction.invalid && cursor_row != Some(buffer.len()) {
+                line = line
+                    .into_iter()
+                    .map(|span| Span::styled(span.content, theme.invalid))
+                    .collect();
+            }
             buffer.push(Spans::from(line));
             line = Vec::new();
         }
     }
 
     pub fn get_asm(&mut self) -> &Vec<Spans<'a>> {
+        let file_index = self.tabs.tabs[self.tabs.index].fileitem_index;
+        let print_height = self.tabs.tabs[self.tabs.index].print_height as u64;
+        let block = &self.files.files[file_index].block;
+        let block_end = block.offset + block.data().len() as u64;
+        let lookahead = self
+            .files
+            .read_range(file_index, block_end, ASM_LOOKAHEAD * (print_height + 1))
+            .unwrap_or_default();
         let cache = &mut self.cache;
         let theme = self.theme;
-        let file_index = self.tabs.tabs[self.tabs.index].fileitem_index;
         let asm_display = self.tabs.tabs[self.tabs.index].asm_display;
+        let ti = &self.tabs.tabs[self.tabs.index];
         let fi = &self.files.files[file_index];
+        let bitness = match ti.asm_arch {
+            AsmArch::X86 => 32,
+            AsmArch::X86_64 => 64,
+            arch => {
+                // tabs restored from a session may name an arch not created yet
+                if self.disassembler.as_ref().map(|(a, _)| *a) != Some(arch) {
+                    self.disassembler = disasm::other(arch).ok().map(|d| (arch, d));
+                }
+                match self.disassembler.as_mut() {
+                    Some((_, disassembler)) => {
+                        Self::get_asm_fmt(fi, cache, theme, disassembler.as_mut(), ti, &lookahead)
+                    }
+                    None => {
+                        cache.buffer.clear();
+                        cache.asm.clear();
+                    }
+                }
+                return &self.cache.buffer;
+            }
+        };
         match asm_display {
-            AsmDisplay::Nasm => Self::get_asm_fmt(fi, cache, theme, &mut self.nasm_formatter),
-            AsmDisplay::Masm => Self::get_asm_fmt(fi, cache, theme, &mut self.masm_formatter),
-            AsmDisplay::Gas => Self::get_asm_fmt(fi, cache, theme, &mut self.gas_formatter),
-            AsmDisplay::Intel => Self::get_asm_fmt(fi, cache, theme, &mut self.intel_formatter),
+            AsmDisplay::Nasm => {
+                let mut x86 = disasm::X86 {
+                    bitness,
+                    formatter: &mut self.nasm_formatter,
+                };
+                Self::get_asm_fmt(fi, cache, theme, &mut x86, ti, &lookahead)
+            }
+            AsmDisplay::Masm => {
+                let mut x86 = disasm::X86 {
+                    bitness,
+                    formatter: &mut self.masm_formatter,
+                };
+                Self::get_asm_fmt(fi, cache, theme, &mut x86, ti, &lookahead)
+            }
+            AsmDisplay::Gas => {
+                let mut x86 = disasm::X86 {
+                    bitness,
+                    formatter: &mut self.gas_formatter,
+                };
+                Self::get_asm_fmt(fi, cache, theme, &mut x86, ti, &lookahead)
+            }
+            AsmDisplay::Intel => {
+                let mut x86 = disasm::X86 {
+                    bitness,
+                    formatter: &mut self.intel_formatter,
+                };
+                Self::get_asm_fmt(fi, cache, theme, &mut x86, ti, &lookahead)
+            }
         }
         &self.cache.buffer
     }
@@ -715,104 +1436,301 @@ impl<'a> App<'a> {
         }
     }
 
+    fn print_gutter(&self) -> (PrintGutter, u64) {
+        let ti = &self.tabs.tabs[self.tabs.index];
+        (ti.print_gutter, ti.rel_base)
+    }
+
+    // prefixes a print view line with its start offset or its line number in the block
+    fn push_print_line(
+        buffer: &mut Vec<Spans<'a>>,
+        mut line: Vec<Span<'a>>,
+        gutter: PrintGutter,
+        start: u64,
+        rel_base: u64,
+        theme: Theme,
+    ) {
+        let label = match gutter {
+            PrintGutter::Off => None,
+            PrintGutter::Offset => Some(format!("{} ", rel_offset(start, rel_base))),
+            PrintGutter::Line => Some(format!("{:>10} ", buffer.len() + 1)),
+        };
+        if let Some(label) = label {
+            line.insert(0, Span::styled(label, theme.offset));
+        }
+        buffer.push(Spans::from(line));
+    }
+
     pub fn get_ascii_print(&mut self) -> &Vec<Spans<'a>> {
         let theme = self.theme;
+        let placeholder = self.placeholder;
+        let tab_width = self.tab_width;
+        let (gutter, rel_base) = self.print_gutter();
+        let cursor = if self.is_text_insert() {
+            self.text_cursor()
+        } else {
+            None
+        };
+        let cursor_style = if self.tabs.current().insert_error {
+            theme.error
+        } else {
+            theme.current_text
+        };
         let fi = self.files.current(self.tabs.file_index());
         let buffer = &mut self.cache.buffer;
         let mut line = Vec::new();
-        let iter = fi.block.buffer.iter();
+        let mut column = 0usize;
+        let mut start = fi.block.offset;
+        let iter = fi.block.data().iter();
 
         buffer.clear();
-        for val in iter {
+        for (i, val) in iter.enumerate() {
+            let at_cursor = cursor == Some(i);
             if *val == b'\n' {
-                buffer.push(Spans::from(line.clone()));
+                if at_cursor {
+                    line.push(Span::styled(" ".to_string(), cursor_style));
+                }
+                Self::push_print_line(buffer, line.clone(), gutter, start, rel_base, theme);
                 line.clear();
+                column = 0;
+                start = fi.block.offset + i as u64 + 1;
             } else if *val == b'\t' {
-                line.push(Span::styled("..", theme.tab));
-            } else if val.is_ascii_graphic() || val.is_ascii_whitespace() {
-                line.push(Span::styled(format!("{}", *val as char), theme.text));
+                // pad up to the next tab stop, dotted so the tab stays visible
+                let width = tab_width - column % tab_width;
+                let style = if at_cursor { cursor_style } else { theme.tab };
+                line.push(Span::styled(".".repeat(width), style));
+                column += width;
             } else {
-                line.push(Span::styled(" ", theme.text));
+                let style = if at_cursor { cursor_style } else { theme.text };
+                if val.is_ascii_graphic() || val.is_ascii_whitespace() {
+                    line.push(Span::styled(format!("{}", *val as char), style));
+                } else {
+                    line.push(Span::styled(placeholder.to_string(), style));
+                }
+                column += 1;
             }
         }
-        buffer.push(Spans::from(line));
+        Self::push_print_line(buffer, line, gutter, start, rel_base, theme);
         &self.cache.buffer
     }
 
     pub fn get_ascii_escape(&mut self) -> &Vec<Spans<'a>> {
         let theme = self.theme;
+        let (gutter, rel_base) = self.print_gutter();
         let fi = self.files.current(self.tabs.file_index());
         let buffer = &mut self.cache.buffer;
         let mut line = Vec::new();
-        let iter = fi.block.buffer.iter();
+        let mut start = fi.block.offset;
+        let iter = fi.block.data().iter();
 
         buffer.clear();
-        for val in iter {
+        for (i, val) in iter.enumerate() {
             let c = *val as char;
             line.push(Span::styled(c.escape_default().to_string(), theme.text));
             if c == '\n' {
-                buffer.push(Spans::from(line.clone()));
+                Self::push_print_line(buffer, line.clone(), gutter, start, rel_base, theme);
                 line.clear();
+                start = fi.block.offset + i as u64 + 1;
             }
         }
-        buffer.push(Spans::from(line));
+        Self::push_print_line(buffer, line, gutter, start, rel_base, theme);
         &self.cache.buffer
     }
 
     pub fn get_unicode_print(&mut self) -> &Vec<Spans<'a>> {
         let theme = self.theme;
+        let (gutter, rel_base) = self.print_gutter();
         let fi = self.files.current(self.tabs.file_index());
         let buffer = &mut self.cache.buffer;
         let mut line = Vec::new();
-        let iter = fi.block.buffer.iter();
+        let mut start = fi.block.offset;
+        let iter = fi.block.data().iter();
 
         buffer.clear();
-        for val in iter {
+        for (i, val) in iter.enumerate() {
             let c = *val as char;
             line.push(Span::styled(format!("{}", c), theme.text));
             if c == '\n' {
-                buffer.push(Spans::from(line.clone()));
+                Self::push_print_line(buffer, line.clone(), gutter, start, rel_base, theme);
                 line.clear();
+                start = fi.block.offset + i as u64 + 1;
             }
         }
-        buffer.push(Spans::from(line));
+        Self::push_print_line(buffer, line, gutter, start, rel_base, theme);
         &self.cache.buffer
     }
 
     pub fn get_unicode_escape(&mut self) -> &Vec<Spans<'a>> {
         let theme = self.theme;
+        let (gutter, rel_base) = self.print_gutter();
         let fi = self.files.current(self.tabs.file_index());
         let buffer = &mut self.cache.buffer;
         let mut line = Vec::new();
-        let iter = fi.block.buffer.iter();
+        let mut start = fi.block.offset;
+        let iter = fi.block.data().iter();
 
         buffer.clear();
-        for val in iter {
+        for (i, val) in iter.enumerate() {
             let c = *val as char;
             line.push(Span::styled(c.escape_unicode().to_string(), theme.text));
             if c == '\n' {
-                buffer.push(Spans::from(line.clone()));
+                Self::push_print_line(buffer, line.clone(), gutter, start, rel_base, theme);
                 line.clear();
+                start = fi.block.offset + i as u64 + 1;
             }
         }
-        buffer.push(Spans::from(line));
+        Self::push_print_line(buffer, line, gutter, start, rel_base, theme);
         &self.cache.buffer
     }
 
-    fn set_block_size(&mut self, ret: Result<u64, ParseIntError>) {
-        if !self.files.files.is_empty() && ret.is_ok() {
-            let mut fi = self.files.current(self.tabs.file_index());
-            let size = ret.unwrap();
-            if size > 0 {
-                fi.block.size = size;
+    fn parse_number_arg(&self, print: &mut Print, what: &str, input: &str) -> Option<u64> {
+        match Self::parse_u64_number(input) {
+            Ok(value) => Some(value),
+            Err(err) => {
+                print.history.print(
+                    self.theme.error,
+                    format!("invalid {}: {}: {}", what, input, err),
+                );
+                None
+            }
+        }
+    }
+
+    fn set_block_size(&mut self, print: &mut Print, input: &str) {
+        if self.files.files.is_empty() {
+            return;
+        }
+        if let Some(size) = self.parse_number_arg(print, "block size", input) {
+            if size == 0 || size > MAX_BLOCK_SIZE {
+                print.history.print(
+                    self.theme.error,
+                    format!("block size must be between 1 and {}", MAX_BLOCK_SIZE),
+                );
+            } else {
+                self.tabs.current().block_size = size;
             }
         }
     }
 
-    fn set_block_offset(&mut self, ret: Result<u64, ParseIntError>) {
-        if !self.files.files.is_empty() && ret.is_ok() {
+    fn set_block_offset(&mut self, print: &mut Print, input: &str) {
+        if self.files.files.is_empty() {
+            return;
+        }
+        if let Some(offset) = self.parse_number_arg(print, "offset", input) {
             let mut fi = self.files.current(self.tabs.file_index());
-            fi.block.offset = ret.unwrap();
+            fi.block.offset = offset;
+        }
+    }
+
+    fn set_cursor(&mut self, print: &mut Print, input: &str) {
+        if self.files.files.is_empty() || self.tabs.tabs.is_empty() {
+            return;
+        }
+        if self.tabs.current().display != Display::Element {
+            print.history.print(
+                self.theme.error,
+                "cursor needs the element view".to_string(),
+            );
+            return;
+        }
+        let offset = match self.parse_number_arg(print, "offset", input) {
+            Some(offset) => offset,
+            None => return,
+        };
+        let index = self.tabs.file_index();
+        let size = self.files.current(index).size;
+        if offset >= size {
+            print.history.print(
+                self.theme.error,
+                format!("offset 0x{:x} is past the end 0x{:x}", offset, size),
+            );
+            return;
+        }
+        let ti = self.tabs.current();
+        let element_size = element_display_size(ti.element_display) as u64;
+        let row_size = element_size
+            * std::cmp::max((ti.print_width as u64 + element_size - 1) / element_size, 1);
+        let page_size = row_size * ti.print_height as u64;
+        let offset = offset & !(element_size - 1);
+        let fi = self.files.current(index);
+        // only scroll when the offset is outside the page, then put its row on top
+        if offset < fi.block.offset || offset - fi.block.offset >= page_size {
+            fi.block.offset = offset - offset % row_size;
+        }
+        let delta = offset - fi.block.offset;
+        let ti = self.tabs.current();
+        ti.insert_mode = true;
+        ti.insert_index = 0;
+        ti.cursor_row = (delta / row_size) as u16;
+        ti.cursor_column = (delta % row_size) as u16;
+        print
+            .history
+            .print(self.theme.text, format!("Cursor at 0x{:x}", offset));
+    }
+
+    fn set_cursors(&mut self, print: &mut Print, inputs: &[&str]) {
+        if self.files.files.is_empty() || self.tabs.tabs.is_empty() {
+            return;
+        }
+        let stride = self.parse_number_arg(print, "stride", inputs[2]);
+        let count = self.parse_number_arg(print, "count", inputs[3]);
+        let (stride, count) = match (stride, count) {
+            (Some(stride), Some(count)) => (stride, count),
+            _ => return,
+        };
+        if count == 0 || count > MAX_CURSORS || (stride == 0 && count > 1) {
+            print.history.print(
+                self.theme.error,
+                format!(
+                    "count must be between 1 and {} with a stride above 0",
+                    MAX_CURSORS
+                ),
+            );
+            return;
+        }
+        self.tabs.current().cursors.clear();
+        self.set_cursor(print, inputs[1]);
+        if !self.is_insert_mode() {
+            return;
+        }
+        let start = match self.cursor_pos() {
+            Some(start) => start,
+            None => return,
+        };
+        let cursors = (0..count)
+            .map_while(|i| i.checked_mul(stride).and_then(|d| start.checked_add(d)))
+            .collect();
+        self.tabs.current().cursors = cursors;
+        print.history.print(
+            self.theme.text,
+            format!("{} cursors every 0x{:x} bytes", count, stride),
+        );
+    }
+
+    // repeats an edit made at the main cursor at the same place relative to every other cursor
+    fn batch_edit(&mut self, key: u64, bytes: &[u8]) {
+        let cursors = &self.tabs.tabs[self.tabs.index].cursors;
+        let anchor = match cursors.first() {
+            Some(anchor) => *anchor as i128,
+            None => return,
+        };
+        let shift = key as i128 - anchor;
+        let targets: Vec<u64> = cursors
+            .iter()
+            .map(|c| *c as i128 + shift)
+            .filter(|t| *t >= 0 && *t != key as i128)
+            .map(|t| t as u64)
+            .collect();
+        let index = self.tabs.file_index();
+        for target in targets {
+            let before = match self.files.read_range(index, target, bytes.len() as u64) {
+                Ok(before) if before.len() == bytes.len() => before,
+                _ => continue,
+            };
+            let fi = self.files.current(index);
+            fi.undo.push(Data::new(target, before));
+            fi.undo.push(Data::new(target, bytes.to_vec()));
+            Self::do_update_patch(&mut fi.patch, target, bytes.to_vec());
         }
     }
 
@@ -826,12 +1744,10 @@ impl<'a> App<'a> {
     }
 
     fn read_block(&mut self) -> io::Result<()> {
-        let path = &self.files.current_path(&mut self.tabs);
-        let mut file = std::fs::File::open(path)?;
-        let len = fs::metadata(path)?.len();
-        let mut fi = self.files.current(self.tabs.file_index());
-        Files::read_block(
-            &mut file,
+        let fi = self.files.current(self.tabs.file_index());
+        let (mut reader, len) = fi.source.reader(&fi.path)?;
+        fi.block.data_len = Files::read_block(
+            &mut reader,
             fi.block.size,
             fi.block.offset,
             len,
@@ -848,6 +1764,15 @@ impl<'a> App<'a> {
         !self.tabs.tabs.is_empty() && self.tabs.current().insert_mode
     }
 
+    fn max_offset(size: u64, pw: usize, ph: u16) -> u64 {
+        if size == 0 {
+            0
+        } else {
+            let last_row = (size - 1) / pw as u64 * pw as u64;
+            last_row.saturating_sub(pw as u64 * ph.saturating_sub(1) as u64)
+        }
+    }
+
     fn on_up(&mut self, print: &mut Print) {
         if self.is_insert_mode() {
             if self.tabs.current().cursor_row > 0 {
@@ -856,10 +1781,16 @@ impl<'a> App<'a> {
             self.tabs.current().insert_index = 0;
         } else if self.show_history {
             print.history.scroll_up(1);
+        } else if self.show_hits {
+            self.prev_hit(print, true, 1);
         } else if !self.files.files.is_empty() {
             let pw = self.tabs.current().print_width;
+            let ph = self.tabs.current().print_height;
+            let wrap = self.tabs.current().wrap_navigation;
             let mut fi = self.files.current(self.tabs.file_index());
-            if fi.block.offset >= pw as u64 {
+            if fi.block.offset == 0 && wrap {
+                fi.block.offset = Self::max_offset(fi.size, pw, ph);
+            } else if fi.block.offset >= pw as u64 {
                 fi.block.offset -= pw as u64;
             } else {
                 fi.block.offset = 0u64;
@@ -875,29 +1806,172 @@ impl<'a> App<'a> {
             self.tabs.current().insert_index = 0;
         } else if self.show_history {
             print.history.scroll_down(1);
+        } else if self.show_hits {
+            self.next_hit(print, true, 1);
         } else if !self.files.files.is_empty() {
             let pw = self.tabs.current().print_width;
+            let ph = self.tabs.current().print_height;
+            let wrap = self.tabs.current().wrap_navigation;
             let mut fi = self.files.current(self.tabs.file_index());
-            if fi.block.offset < u64::MAX - pw as u64 {
-                fi.block.offset += pw as u64;
+            let max = Self::max_offset(fi.size, pw, ph);
+            if fi.block.offset >= max {
+                if wrap {
+                    fi.block.offset = 0u64;
+                }
             } else {
-                fi.block.offset = u64::MAX;
+                fi.block.offset = std::cmp::min(fi.block.offset + pw as u64, max);
             }
         }
     }
 
-    fn on_pageup(&mut self, print: &mut Print) {
-        if self.is_insert_mode() {
-            self.tabs.current().insert_index = 0;
-        } else if self.show_history {
-            print.history.scroll_up(20);
-        } else if !self.files.files.is_empty() {
-            let pw = self.tabs.current().print_width;
-            let ph = self.tabs.current().print_height;
-            let mut fi = self.files.current(self.tabs.file_index());
-            let size = (pw as u64) * (ph as u64);
-            if fi.block.offset >= size {
-                fi.block.offset -= size;
+    fn on_step(&mut self, forward: bool) {
+        if self.files.files.is_empty() || self.tabs.tabs.is_empty() {
+            return;
+        }
+        let pw = self.tabs.current().print_width;
+        let ph = self.tabs.current().print_height;
+        let step = self.tabs.current().step;
+        let fi = self.files.current(self.tabs.file_index());
+        if forward {
+            let max = Self::max_offset(fi.size, pw, ph);
+            if fi.block.offset < max {
+                fi.block.offset = std::cmp::min(fi.block.offset.saturating_add(step), max);
+            }
+        } else {
+            fi.block.offset = fi.block.offset.saturating_sub(step);
+        }
+    }
+
+    fn align_offset(&mut self, print: &mut Print, up: bool, input: &str) {
+        if self.files.files.is_empty() || self.tabs.tabs.is_empty() {
+            return;
+        }
+        let align = match self.parse_number_arg(print, "alignment", input) {
+            Some(align) if align.is_power_of_two() => align,
+            Some(_) => {
+                print.history.print(
+                    self.theme.error,
+                    "alignment must be a power of two".to_string(),
+                );
+                return;
+            }
+            None => return,
+        };
+        let fi = self.files.current(self.tabs.file_index());
+        let rem = fi.block.offset % align;
+        if up && rem != 0 {
+            fi.block.offset = std::cmp::min(fi.block.offset.saturating_add(align - rem), fi.size);
+        } else {
+            fi.block.offset -= rem;
+        }
+        let offset = fi.block.offset;
+        print.history.print(
+            self.theme.text,
+            format!("Offset aligned to 0x{:08x}", offset),
+        );
+    }
+
+    fn set_step(&mut self, print: &mut Print, input: &str) {
+        if self.tabs.tabs.is_empty() {
+            return;
+        }
+        match self.parse_number_arg(print, "step", input) {
+            Some(0) => print
+                .history
+                .print(self.theme.error, "step must be above 0".to_string()),
+            Some(step) => self.tabs.current().step = step,
+            None => {}
+        }
+    }
+
+    fn set_rel_base(&mut self, print: &mut Print, input: &str) {
+        if self.tabs.tabs.is_empty() {
+            return;
+        }
+        if let Some(base) = self.parse_number_arg(print, "relbase", input) {
+            self.tabs.current().rel_base = base;
+        }
+    }
+
+    fn set_placeholder(&mut self, print: &mut Print, input: &str) {
+        let mut chars = input.chars();
+        let placeholder = match (chars.next(), chars.next()) {
+            (Some(c), None) if c.is_ascii_graphic() => Some(c),
+            _ => PLACEHOLDERS
+                .iter()
+                .find(|(name, c)| name.eq(&input) || c.to_string().eq(input))
+                .map(|(_, c)| *c),
+        };
+        match placeholder {
+            Some(c) => self.placeholder = c,
+            None => {
+                let names: Vec<&str> = PLACEHOLDERS.iter().map(|(name, _)| *name).collect();
+                print.history.print(
+                    self.theme.error,
+                    format!(
+                        "placeholder must be a printable ascii character or one of: {}",
+                        names.join(", ")
+                    ),
+                );
+            }
+        }
+    }
+
+    fn set_tab_width(&mut self, print: &mut Print, input: &str) {
+        match self.parse_number_arg(print, "tab width", input) {
+            Some(width) if (1..=MAX_TAB_WIDTH).contains(&width) => self.tab_width = width as usize,
+            Some(_) => print.history.print(
+                self.theme.error,
+                format!("tab width must be between 1 and {}", MAX_TAB_WIDTH),
+            ),
+            None => {}
+        }
+    }
+
+    fn set_undo_limit(&mut self, print: &mut Print, input: &str) {
+        if let Some(limit) = self.parse_number_arg(print, "undo limit", input) {
+            self.files.set_undo_limit(limit as usize);
+        }
+    }
+
+    fn set_rows(&mut self, print: &mut Print, input: &str) {
+        if self.tabs.tabs.is_empty() {
+            return;
+        }
+        if input.eq("auto") {
+            self.tabs.current().rows = 0;
+            print
+                .history
+                .print(self.theme.text, "Rows follow terminal height".to_string());
+            return;
+        }
+        if let Some(rows) = self.parse_number_arg(print, "rows", input) {
+            let rows = rows.clamp(1, u16::MAX as u64) as u16;
+            let ti = self.tabs.current();
+            ti.rows = rows;
+            ti.print_height = rows;
+            ti.cursor_row = ti.cursor_row.min(rows - 1);
+            print
+                .history
+                .print(self.theme.text, format!("Rows fixed at {}", rows));
+        }
+    }
+
+    fn on_pageup(&mut self, print: &mut Print) {
+        if self.is_insert_mode() {
+            self.tabs.current().insert_index = 0;
+        } else if self.show_history {
+            print.history.scroll_up(20);
+        } else if !self.files.files.is_empty() {
+            let pw = self.tabs.current().print_width;
+            let ph = self.tabs.current().print_height;
+            let wrap = self.tabs.current().wrap_navigation;
+            let mut fi = self.files.current(self.tabs.file_index());
+            let size = (pw as u64) * (ph as u64);
+            if fi.block.offset == 0 && wrap {
+                fi.block.offset = Self::max_offset(fi.size, pw, ph);
+            } else if fi.block.offset >= size {
+                fi.block.offset -= size;
             } else {
                 fi.block.offset = 0u64;
             }
@@ -912,18 +1986,24 @@ impl<'a> App<'a> {
         } else if !self.files.files.is_empty() {
             let pw = self.tabs.current().print_width;
             let ph = self.tabs.current().print_height;
+            let wrap = self.tabs.current().wrap_navigation;
             let mut fi = self.files.current(self.tabs.file_index());
             let size = (pw as u64) * (ph as u64);
-            if fi.block.offset < u64::MAX - size {
-                fi.block.offset += size;
+            let max = Self::max_offset(fi.size, pw, ph);
+            if fi.block.offset >= max {
+                if wrap {
+                    fi.block.offset = 0u64;
+                }
             } else {
-                fi.block.offset = u64::MAX;
+                fi.block.offset = std::cmp::min(fi.block.offset.saturating_add(size), max);
             }
         }
     }
 
     fn on_right(&mut self, _print: &mut Print) {
-        if self.is_insert_mode() {
+        if self.is_text_insert() {
+            self.text_cursor_step(true);
+        } else if self.is_insert_mode() {
             self.tabs.cursor_right();
             self.tabs.current().insert_index = 0;
         } else {
@@ -932,7 +2012,9 @@ impl<'a> App<'a> {
     }
 
     fn on_left(&mut self, _print: &mut Print) {
-        if self.is_insert_mode() {
+        if self.is_text_insert() {
+            self.text_cursor_step(false);
+        } else if self.is_insert_mode() {
             self.tabs.cursor_left();
             self.tabs.current().insert_index = 0;
         } else {
@@ -948,22 +2030,26 @@ impl<'a> App<'a> {
         self.show_history = !self.show_history;
     }
 
-    fn on_end(&mut self, _print: &mut Print) {
+    fn on_end(&mut self, print: &mut Print) {
         if self.is_insert_mode() {
             let mut ti = &mut self.tabs.tabs[self.tabs.index];
             ti.cursor_column = (ti.print_width - 1) as u16;
             ti.cursor_row = ti.print_height - 1;
+        } else if self.show_history {
+            print.history.scroll_oldest();
         } else if !self.files.files.is_empty() && !self.tabs.tabs.is_empty() {
             let mut fi = self.files.current(self.tabs.file_index());
             fi.block.offset = fi.size;
         }
     }
 
-    fn on_home(&mut self, _print: &mut Print) {
+    fn on_home(&mut self, print: &mut Print) {
         if self.is_insert_mode() {
             let mut ti = &mut self.tabs.tabs[self.tabs.index];
             ti.cursor_column = 0;
             ti.cursor_row = 0;
+        } else if self.show_history {
+            print.history.scroll_newest();
         } else if !self.files.files.is_empty() && !self.tabs.tabs.is_empty() {
             self.files.current(self.tabs.file_index()).block.offset = 0;
         }
@@ -971,7 +2057,10 @@ impl<'a> App<'a> {
 
     fn on_insert(&mut self, _print: &mut Print) {
         if !self.tabs.tabs.is_empty() {
-            if self.tabs.current().display == Display::Element {
+            let display = self.tabs.current().display;
+            let text = display == Display::Print
+                && self.tabs.current().print_display == PrintDisplay::ASCIIPrint;
+            if display == Display::Element || display == Display::Asm || text {
                 self.tabs.current().insert_mode = !self.tabs.current().insert_mode;
             }
         }
@@ -1092,54 +2181,196 @@ impl<'a> App<'a> {
         }
     }
 
-    fn next_hit(&mut self, modifier: KeyModifiers) {
+    fn next_theme(&mut self) {
+        self.theme_index = (self.theme_index + 1).rem_euclid(THEMES.len());
+        self.theme = Theme::preset(THEMES[self.theme_index]).unwrap();
+    }
+
+    fn set_theme(&mut self, print: &mut Print, name: String) {
+        if let Some(index) = THEMES.iter().position(|t| name.eq(t)) {
+            self.theme_index = index;
+            self.theme = Theme::preset(&name).unwrap();
+        } else {
+            print.history.print(
+                self.theme.error,
+                format!("Unknown theme {}, valid: {}", name, THEMES.join(" ")),
+            );
+        }
+    }
+
+    // moves count hits or groups at once, so a count prefix logs only where it lands
+    fn next_hit(&mut self, print: &mut Print, group: bool, count: usize) {
         if !self.tabs.tabs.is_empty() && !self.files.files.is_empty() {
             let fi = &mut self.files.current(self.tabs.file_index());
-            if modifier != KeyModifiers::CONTROL {
-                let hits = &mut fi.hhits.hits[fi.hhits.selected];
-                if !hits.is_empty() {
-                    hits.selected = (hits.selected + 1) % hits.hits.len();
-                    fi.block.offset = hits.hits[hits.selected];
+            if !group {
+                if let Some(hits) = fi.hhits.hits.get_mut(fi.hhits.selected) {
+                    if !hits.is_empty() {
+                        hits.selected = (hits.selected + count) % hits.hits.len();
+                        fi.block.offset = hits.hits[hits.selected];
+                    }
                 }
             } else if !fi.hhits.hits.is_empty() {
-                fi.hhits.selected = (fi.hhits.selected + 1) % fi.hhits.hits.len();
+                fi.hhits.selected = (fi.hhits.selected + count) % fi.hhits.hits.len();
                 let hits = &mut fi.hhits.hits[fi.hhits.selected];
                 if !hits.is_empty() {
                     fi.block.offset = hits.hits[hits.selected];
                 }
             }
         }
+        self.log_hit(print);
     }
 
-    fn prev_hit(&mut self, modifier: KeyModifiers) {
+    fn prev_hit(&mut self, print: &mut Print, group: bool, count: usize) {
         if !self.tabs.tabs.is_empty() && !self.files.files.is_empty() {
             let fi = &mut self.files.current(self.tabs.file_index());
-            if modifier != KeyModifiers::CONTROL {
-                let hits = &mut fi.hhits.hits[fi.hhits.selected];
-                if !hits.is_empty() {
-                    if hits.selected > 0 {
-                        hits.selected -= 1;
-                    } else {
-                        hits.selected = hits.hits.len() - 1;
+            if !group {
+                if let Some(hits) = fi.hhits.hits.get_mut(fi.hhits.selected) {
+                    if !hits.is_empty() {
+                        let len = hits.hits.len();
+                        hits.selected = (hits.selected + len - count % len) % len;
+                        fi.block.offset = hits.hits[hits.selected];
                     }
-                    fi.block.offset = hits.hits[hits.selected];
                 }
             } else if !fi.hhits.hits.is_empty() {
-                if fi.hhits.selected > 0 {
-                    fi.hhits.selected -= 1;
-                } else {
-                    fi.hhits.selected = fi.hhits.hits.len() - 1;
-                }
+                let len = fi.hhits.hits.len();
+                fi.hhits.selected = (fi.hhits.selected + len - count % len) % len;
                 let hits = &mut fi.hhits.hits[fi.hhits.selected];
                 if !hits.is_empty() {
                     fi.block.offset = hits.hits[hits.selected];
                 }
             }
         }
+        self.log_hit(print);
+    }
+
+    // offset, pattern and a few surrounding bytes of the selected hit
+    fn log_hit(&mut self, print: &mut Print) {
+        if self.tabs.tabs.is_empty() || self.files.files.is_empty() {
+            return;
+        }
+        let index = self.tabs.file_index();
+        let fi = self.files.current(index);
+        let hits = match fi.hhits.hits.get(fi.hhits.selected) {
+            Some(hits) if !hits.hits.is_empty() => hits,
+            _ => return,
+        };
+        let (selected, count, flag, len) =
+            (hits.selected, hits.hits.len(), hits.flag.clone(), hits.len);
+        let offset = hits.hits[selected];
+        let start = offset.saturating_sub(HIT_CONTEXT);
+        let context = self
+            .files
+            .read_range(index, start, offset - start + len + HIT_CONTEXT)
+            .unwrap_or_default();
+        let mut bytes = Vec::with_capacity(context.len());
+        for (i, b) in context.iter().enumerate() {
+            let pos = start + i as u64;
+            if pos == offset {
+                bytes.push(format!("[{:02x}", b));
+            } else {
+                bytes.push(format!("{:02x}", b));
+            }
+            if pos + 1 == offset + len {
+                bytes.last_mut().unwrap().push(']');
+            }
+        }
+        print.history.print(
+            self.theme.text,
+            format!(
+                "hit {} of {} at 0x{:08x} for {}: {}",
+                selected + 1,
+                count,
+                offset,
+                flag,
+                bytes.join(" ")
+            ),
+        );
+    }
+
+    fn goto_hit(&mut self, print: &mut Print, which: &str) {
+        if self.tabs.tabs.is_empty() || self.files.files.is_empty() {
+            return;
+        }
+        let count = {
+            let fi = self.files.current(self.tabs.file_index());
+            match fi.hhits.hits.get(fi.hhits.selected) {
+                Some(hits) => hits.hits.len(),
+                None => 0,
+            }
+        };
+        if count == 0 {
+            print
+                .history
+                .print(self.theme.error, "No search hits selected".to_string());
+            return;
+        }
+        let index = if which.eq("first") {
+            0
+        } else if which.eq("last") {
+            count - 1
+        } else {
+            match self.parse_number_arg(print, "hit index", which) {
+                Some(n) if n >= 1 && n <= count as u64 => n as usize - 1,
+                Some(n) => {
+                    print.history.print(
+                        self.theme.error,
+                        format!("hit index {} out of range 1..{}", n, count),
+                    );
+                    return;
+                }
+                None => return,
+            }
+        };
+        let fi = self.files.current(self.tabs.file_index());
+        let hits = &mut fi.hhits.hits[fi.hhits.selected];
+        hits.selected = index;
+        fi.block.offset = hits.hits[index];
+        print.history.print(
+            self.theme.text,
+            format!(
+                "hit {} of {} at 0x{:08x}",
+                index + 1,
+                count,
+                fi.block.offset
+            ),
+        );
+    }
+
+    fn remove_hit_group(&mut self, print: &mut Print) {
+        if self.tabs.tabs.is_empty() || self.files.files.is_empty() {
+            return;
+        }
+        let fi = self.files.current(self.tabs.file_index());
+        if let Some(hits) = fi.hhits.remove() {
+            print.history.print(
+                self.theme.text,
+                format!("Removed {} hits for {}", hits.hits.len(), hits.flag),
+            );
+        }
+    }
+
+    pub fn get_hit_groups(&mut self) -> Vec<Spans<'a>> {
+        let mut text = vec![Spans::from("Search hit groups")];
+        if self.tabs.tabs.is_empty() || self.files.files.is_empty() {
+            return text;
+        }
+        let fi = self.files.current(self.tabs.file_index());
+        for (i, hits) in fi.hhits.hits.iter().enumerate() {
+            let style = if i == fi.hhits.selected {
+                self.theme.current_text
+            } else {
+                self.theme.text
+            };
+            text.push(Spans::from(Span::styled(
+                format!("{:>4} {:<32} {} hits", i + 1, hits.flag, hits.hits.len()),
+                style,
+            )));
+        }
+        text
     }
 
     fn do_flush_input(
-        input: [u8; 64],
+        input: [u8; INSERT_VECTOR_SIZE],
         size: u16,
         display_size: u16,
         base: u32,
@@ -1159,16 +2390,370 @@ impl<'a> App<'a> {
             8 => {
                 flush_input_item!(u64, size, input, base, vv, r);
             }
+            16 => {
+                flush_input_item!(u128, size, input, base, vv, r);
+            }
             _ => {}
         }
         r
     }
 
     fn do_update_patch(patch: &mut BTreeMap<u64, Vec<u8>>, offset: u64, value: Vec<u8>) {
-        patch.insert(offset, value);
+        let end = offset + value.len() as u64;
+        let r = patch.range_mut(offset.saturating_sub(MAX_PATCH_SIZE)..end);
+        for (key, data) in r {
+            let data_end = key + data.len() as u64;
+            if data_end <= offset {
+                continue;
+            }
+            let start = std::cmp::max(*key, offset);
+            let stop = std::cmp::min(data_end, end);
+            data[(start - key) as usize..(stop - key) as usize]
+                .copy_from_slice(&value[(start - offset) as usize..(stop - offset) as usize]);
+        }
+        if patch
+            .get(&offset)
+            .is_none_or(|data| data.len() < value.len())
+        {
+            patch.insert(offset, value);
+        }
+    }
+
+    fn parse_hex_bytes(input: &str) -> Option<Vec<u8>> {
+        let input = input.strip_prefix("0x").unwrap_or(input);
+        if input.is_empty() || !input.len().is_multiple_of(2) {
+            return None;
+        }
+        (0..input.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(input.get(i..i + 2)?, 16).ok())
+            .collect()
+    }
+
+    fn parse_range(&self, print: &mut Print, start: &str, end: &str) -> Option<(u64, u64)> {
+        let start = self.parse_number_arg(print, "start offset", start);
+        let end = self.parse_number_arg(print, "end offset", end);
+        match (start, end) {
+            (Some(start), Some(end)) if start < end => Some((start, end)),
+            (Some(_), Some(_)) => {
+                print.history.print(
+                    self.theme.error,
+                    "start offset must be below end offset".to_string(),
+                );
+                None
+            }
+            _ => None,
+        }
+    }
+
+    fn patch_range<F>(&mut self, print: &mut Print, start: u64, end: u64, mut f: F) -> usize
+    where
+        F: FnMut(usize, &mut [u8]),
+    {
+        let index = self.tabs.file_index();
+        let mut pos = start;
+        let mut done = 0usize;
+        self.files.current(index).begin_edit();
+        while pos < end {
+            let size = std::cmp::min(MAX_PATCH_SIZE, end - pos);
+            let before = match self.files.read_range(index, pos, size) {
+                Ok(bytes) => bytes,
+                Err(err) => {
+                    print
+                        .history
+                        .print(self.theme.error, format!("Failed to read range: {}", err));
+                    break;
+                }
+            };
+            if before.is_empty() {
+                break;
+            }
+            let mut after = before.clone();
+            f(done, &mut after);
+            let fi = self.files.current(index);
+            // Files::write works one block at a time, so no entry may cross a block boundary
+            let split = std::cmp::min(MAX_PATCH_SIZE - pos % MAX_PATCH_SIZE, size) as usize;
+            let split = std::cmp::min(split, after.len());
+            for (at, range) in [(pos, 0..split), (pos + split as u64, split..after.len())] {
+                if before[range.clone()] == after[range.clone()] {
+                    continue;
+                }
+                fi.undo.push(Data::new(at, before[range.clone()].to_vec()));
+                fi.undo.push(Data::new(at, after[range.clone()].to_vec()));
+                Self::do_update_patch(&mut fi.patch, at, after[range].to_vec());
+            }
+            done += after.len();
+            pos += size;
+        }
+        // an edit that put back the original bytes leaves the file clean
+        let fi = self.files.current(index);
+        Files::do_apply_patch(&mut fi.block, &fi.patch);
+        Files::prune_patch(fi);
+        if done == 0 {
+            print.history.print(
+                self.theme.error,
+                format!("0x{:08x} is beyond end of file", start),
+            );
+        }
+        done
+    }
+
+    fn handle_decode(&mut self, print: &mut Print, inputs: &[&str]) {
+        if self.files.files.is_empty() || self.tabs.tabs.is_empty() {
+            return;
+        }
+        if !self.check_keyword(print, "encoding", inputs[1], &["base64", "hex"]) {
+            return;
+        }
+        let (start, end, _) = match self.range_args(print, inputs[0], &inputs[2..], 0) {
+            Some(range) => range,
+            None => return,
+        };
+        let index = self.tabs.file_index();
+        let input = match self.files.read_range(index, start, end - start) {
+            Ok(bytes) => bytes,
+            Err(err) => {
+                print
+                    .history
+                    .print(self.theme.error, format!("Failed to read range: {}", err));
+                return;
+            }
+        };
+        let decoded = if inputs[1].eq("base64") {
+            decode::base64(&input)
+        } else {
+            decode::hex(&input)
+        };
+        match decoded {
+            Ok(data) => {
+                let label = format!(
+                    "[{} 0x{:x}-0x{:x} of {}]",
+                    inputs[1],
+                    start,
+                    end,
+                    self.files.current(index).path
+                );
+                print.history.print(
+                    self.theme.text,
+                    format!("Decoded {} bytes into {}", data.len(), label),
+                );
+                self.files.add_memory(label, data, &mut self.tabs);
+                self.tabs.index = self.tabs.tabs.len() - 1;
+            }
+            Err(err) => print
+                .history
+                .print(self.theme.error, format!("decode {}: {}", inputs[1], err)),
+        }
+    }
+
+    // start of every run of bytes where the patched file differs from its source
+    fn scan_changes(&mut self, index: usize) -> io::Result<(Hits, u64)> {
+        let fi = &self.files.files[index];
+        let (mut reader, len) = fi.source.reader(&fi.path)?;
+        let mut hits = Hits::new("changes".to_string(), 1);
+        let mut block = Block::new(CHANGES_BLOCK as usize);
+        let mut source = Vec::new();
+        let mut changed = 0u64;
+        let mut in_run = false;
+        let mut offset = 0u64;
+        while offset < len {
+            let nb_read = Files::read_block(&mut reader, block.size, offset, len, &mut source)?;
+            source.truncate(nb_read);
+            block.offset = offset;
+            block.buffer.clone_from(&source);
+            Files::do_apply_patch(&mut block, &fi.patch);
+            for (i, (a, b)) in source.iter().zip(block.buffer.iter()).enumerate() {
+                if a != b {
+                    if !in_run {
+                        hits.hits.push(offset + i as u64);
+                    }
+                    changed += 1;
+                }
+                in_run = a != b;
+            }
+            if nb_read == 0 {
+                break;
+            }
+            offset += nb_read as u64;
+        }
+        Ok((hits, changed))
+    }
+
+    fn handle_changes(&mut self, print: &mut Print) {
+        if self.files.files.is_empty() || self.tabs.tabs.is_empty() {
+            return;
+        }
+        let index = self.tabs.file_index();
+        let (hits, changed) = match self.scan_changes(index) {
+            Ok(result) => result,
+            Err(err) => {
+                print
+                    .history
+                    .print(self.theme.error, format!("changes failed: {}", err));
+                return;
+            }
+        };
+        print.history.print(
+            self.theme.text,
+            format!(
+                "{} bytes differ from {} in {} ranges",
+                changed,
+                self.files.current(index).path,
+                hits.hits.len()
+            ),
+        );
+        if hits.hits.is_empty() {
+            return;
+        }
+        let fi = self.files.current(index);
+        fi.hhits.add(hits);
+        if let Some(index) = fi.hhits.hits.iter().position(|h| h.flag.eq("changes")) {
+            fi.hhits.selected = index;
+        }
+    }
+
+    fn handle_carve(&mut self, print: &mut Print, inputs: &[&str]) {
+        if self.files.files.is_empty() || self.tabs.tabs.is_empty() {
+            return;
+        }
+        let (start, end, args) = match self.range_args(print, inputs[0], &inputs[1..], 1) {
+            Some(range) => range,
+            None => return,
+        };
+        let index = self.tabs.file_index();
+        let result = self
+            .files
+            .read_range(index, start, end - start)
+            .and_then(|bytes| std::fs::write(args[0], &bytes).map(|_| bytes.len()));
+        match result {
+            Ok(len) => print.history.print(
+                self.theme.text,
+                format!("Carved {} bytes at 0x{:08x} to {}", len, start, args[0]),
+            ),
+            Err(err) => print.history.print(
+                self.theme.error,
+                format!("Failed to carve {}: {}", args[0], err),
+            ),
+        }
+    }
+
+    fn handle_transform(&mut self, print: &mut Print, inputs: &[&str]) {
+        if self.files.files.is_empty() || self.tabs.tabs.is_empty() {
+            return;
+        }
+        let op = inputs[0];
+        let (start, end, args) = match self.range_args(print, inputs[0], &inputs[1..], 1) {
+            Some(range) => range,
+            None => return,
+        };
+        let key = if op.eq("rol") || op.eq("ror") {
+            match self.parse_number_arg(print, "bit count", args[0]) {
+                Some(n) if n < 8 => vec![n as u8],
+                Some(_) => {
+                    print.history.print(
+                        self.theme.error,
+                        "bit count must be between 0 and 7".to_string(),
+                    );
+                    return;
+                }
+                None => return,
+            }
+        } else {
+            match Self::parse_hex_bytes(args[0]) {
+                Some(key) => key,
+                None => {
+                    print.history.print(
+                        self.theme.error,
+                        format!("invalid key: {}: expected hex bytes", args[0]),
+                    );
+                    return;
+                }
+            }
+        };
+        let done = self.patch_range(print, start, end, |done, bytes| {
+            for (i, b) in bytes.iter_mut().enumerate() {
+                let k = key[(done + i) % key.len()];
+                *b = match op {
+                    "xor" => *b ^ k,
+                    "add" => b.wrapping_add(k),
+                    "sub" => b.wrapping_sub(k),
+                    "rol" => b.rotate_left(k as u32),
+                    _ => b.rotate_right(k as u32),
+                };
+            }
+        });
+        if done > 0 {
+            print.history.print(
+                self.theme.text,
+                format!("{} {} bytes at 0x{:08x}", op, done, start),
+            );
+        }
+    }
+
+    fn handle_bswap(&mut self, print: &mut Print, inputs: &[&str]) {
+        if self.files.files.is_empty() || self.tabs.tabs.is_empty() {
+            return;
+        }
+        let (start, end, args) = match self.range_args(print, inputs[0], &inputs[1..], 1) {
+            Some(range) => range,
+            None => return,
+        };
+        let width = match self.parse_number_arg(print, "width", args[0]) {
+            Some(width) if width == 2 || width == 4 || width == 8 => width as usize,
+            Some(width) => {
+                print.history.print(
+                    self.theme.error,
+                    format!("invalid width: {}: expected 2, 4 or 8", width),
+                );
+                return;
+            }
+            None => return,
+        };
+        if (end - start) % width as u64 != 0 {
+            print.history.print(
+                self.theme.error,
+                format!("range length is not a multiple of {}", width),
+            );
+            return;
+        }
+        let done = self.patch_range(print, start, end, |_, bytes| {
+            for group in bytes.chunks_exact_mut(width) {
+                group.reverse();
+            }
+        });
+        if done > 0 {
+            print.history.print(
+                self.theme.text,
+                format!("bswap {} bytes at 0x{:08x}", done, start),
+            );
+        }
+    }
+
+    fn handle_bit(&mut self, c: char) {
+        let bit = match c.to_digit(8) {
+            Some(bit) => bit,
+            None => {
+                self.tabs.current().insert_error = true;
+                return;
+            }
+        };
+        let pos = self.tabs.cursor_pos();
+        let index = self.tabs.file_index();
+        let byte = match self.files.current(index).block.data().get(pos) {
+            Some(byte) => *byte ^ (1 << bit),
+            None => {
+                self.tabs.current().insert_error = true;
+                return;
+            }
+        };
+        self.patch_block(index, pos, vec![byte]);
     }
 
     fn handle_insert(&mut self, c: char) {
+        if self.tabs.current().element_mode == ElementMode::Bit {
+            self.handle_bit(c);
+            return;
+        }
         let mut vv: Vec<u8> = Vec::new();
         let tabs = &mut self.tabs;
         let pos = tabs.cursor_pos();
@@ -1179,159 +2764,1177 @@ impl<'a> App<'a> {
         let insert_index = ti.insert_index;
         let insert_size = Tabs::element_input_size(ti);
         let display_size = element_display_size(element_display);
-        let fi = self.files.current(index);
         let ib = &mut ti.insert_vector;
+        let base = element_mode_base(element_mode);
+        if c != '.' && !c.is_digit(base) {
+            ti.insert_error = true;
+            return;
+        }
+        // the index is left over from a wider element when the display changed under it
+        if insert_index >= insert_size as usize || insert_index >= ib.len() {
+            ti.insert_index = 0;
+            ti.insert_error = true;
+            return;
+        }
+        let old = ib[insert_index];
         if c != '.' {
             ib[insert_index] = c as u8;
         }
-        let base = element_mode_base(element_mode);
-        let patch = &mut fi.patch;
-        let undo = &mut fi.undo;
-        let block = &mut fi.block;
         let got_input = Self::do_flush_input(*ib, insert_size, display_size, base, &mut vv);
-        if got_input {
-            let min = pos;
-            let max = min + vv.len();
-            let key = block.offset + pos as u64;
-            undo.push(Data::new(key, (&block.buffer[min..max]).to_vec()));
-            block.buffer.splice(min..max, vv.clone());
-            undo.push(Data::new(key, (&block.buffer[min..max]).to_vec()));
-            Self::do_update_patch(patch, key, vv);
+        if !got_input {
+            ib[insert_index] = old;
+            ti.insert_error = true;
+            return;
+        }
+        if !self.patch_block(index, pos, vv) {
+            self.tabs.current().insert_vector[insert_index] = old;
+            return;
         }
         self.tabs.insert_index_next();
     }
 
+    // overwrites the block at pos, recording the change in the patch and the undo history,
+    // the padding past the end of file is not editable
+    fn patch_block(&mut self, index: usize, pos: usize, vv: Vec<u8>) -> bool {
+        let fi = self.files.current(index);
+        if pos + vv.len() > fi.block.data().len() {
+            self.tabs.current().insert_error = true;
+            return false;
+        }
+        fi.begin_edit();
+        let block = &mut fi.block;
+        let min = pos;
+        let max = min + vv.len();
+        let key = block.offset + pos as u64;
+        fi.undo
+            .push(Data::new(key, (&block.buffer[min..max]).to_vec()));
+        block.buffer.splice(min..max, vv.clone());
+        fi.undo
+            .push(Data::new(key, (&block.buffer[min..max]).to_vec()));
+        Self::do_update_patch(&mut fi.patch, key, vv.clone());
+        self.batch_edit(key, &vv);
+        Files::prune_patch(self.files.current(index));
+        true
+    }
+
+    fn handle_text_insert(&mut self, c: char) {
+        let pos = match self.text_cursor() {
+            Some(pos) if c.is_ascii() && !c.is_ascii_control() => pos,
+            _ => {
+                self.tabs.current().insert_error = true;
+                return;
+            }
+        };
+        let index = self.tabs.file_index();
+        if self.patch_block(index, pos, vec![c as u8]) {
+            self.text_cursor_step(true);
+        }
+    }
+
+    fn is_text_insert(&mut self) -> bool {
+        self.is_insert_mode()
+            && self.tabs.current().display == Display::Print
+            && self.tabs.current().print_display == PrintDisplay::ASCIIPrint
+    }
+
+    // [start, end) of the row-th line of the block as laid out by the ascii print view
+    fn text_line(&mut self, row: usize) -> Option<(usize, usize)> {
+        let buffer = self.files.current(self.tabs.file_index()).block.data();
+        let mut start = 0;
+        for _ in 0..row {
+            start += buffer.get(start..)?.iter().position(|b| *b == b'\n')? + 1;
+        }
+        let end = buffer[start..]
+            .iter()
+            .position(|b| *b == b'\n')
+            .map_or(buffer.len(), |n| start + n);
+        Some((start, end))
+    }
+
+    // block index of the byte under the text cursor, the newline ending its line included
+    fn text_cursor(&mut self) -> Option<usize> {
+        let ti = self.tabs.current();
+        let (row, column) = (ti.cursor_row as usize, ti.cursor_column as usize);
+        let (start, end) = self.text_line(row)?;
+        // the buffer runs on past the end of file, a last line without a newline stops there
+        let len = self
+            .files
+            .current(self.tabs.file_index())
+            .block
+            .data()
+            .len();
+        let last = if end < len {
+            end
+        } else {
+            end.checked_sub(1).filter(|last| *last >= start)?
+        };
+        Some(std::cmp::min(start + column, last))
+    }
+
+    fn text_cursor_step(&mut self, forward: bool) {
+        let row = self.tabs.current().cursor_row as usize;
+        let (start, cursor) = match (self.text_line(row), self.text_cursor()) {
+            (Some((start, _)), Some(cursor)) => (start, cursor),
+            _ => return,
+        };
+        let column = if forward {
+            cursor - start + 1
+        } else {
+            (cursor - start).saturating_sub(1)
+        };
+        self.tabs.current().cursor_column = std::cmp::min(column, u16::MAX as usize) as u16;
+        // stay on the last byte instead of running past the end of the line
+        if forward && self.text_cursor() == Some(cursor) {
+            self.tabs.current().cursor_column = (cursor - start) as u16;
+        }
+    }
+
+    // each group holds before/after pairs, replayed in reverse the last value applied is
+    // the oldest before, and the reversed group redoes to the newest after
     fn do_undo(&mut self) {
         let fi = self.files.current(self.tabs.file_index());
-        for _i in 0..2 {
-            let opt = fi.undo.pop();
-            if opt.is_some() {
-                let data = opt.unwrap();
+        if let Some(mut group) = fi.undo.pop() {
+            group.reverse();
+            for data in group.iter() {
                 Self::do_update_patch(&mut fi.patch, data.offset, data.data.clone());
-                fi.redo.push(data);
             }
+            fi.redo.push_group(group);
         }
+        // the restored bytes reach the block before pruning drops their entries
+        Files::do_apply_patch(&mut fi.block, &fi.patch);
+        Files::prune_patch(fi);
     }
 
     fn do_redo(&mut self) {
         let fi = self.files.current(self.tabs.file_index());
-        for _i in 0..2 {
-            let opt = fi.redo.pop();
-            if opt.is_some() {
-                let data = opt.unwrap();
+        if let Some(mut group) = fi.redo.pop() {
+            group.reverse();
+            for data in group.iter() {
                 Self::do_update_patch(&mut fi.patch, data.offset, data.data.clone());
-                fi.undo.push(data);
             }
+            fi.undo.push_group(group);
         }
+        Files::do_apply_patch(&mut fi.block, &fi.patch);
+        Files::prune_patch(fi);
     }
 
-    fn on_key(&mut self, print: &mut Print, c: char, modifier: KeyModifiers) {
-        if self.is_insert_mode() {
-            if c.is_ascii_hexdigit() || c == '.' {
-                self.handle_insert(c);
-            } else if c == 'u' {
-                self.do_undo();
-            } else if c == 'U' {
-                self.do_redo();
-            }
-        } else {
-            match c {
-                'Q' => {
-                    self.should_quit = true;
-                }
-                ':' => {
-                    self.enter_prompt = true;
-                }
-                'W' => {
-                    if !self.tabs.tabs.is_empty() {
-                        let r = self.files.write(self.tabs.file_index());
-                        if r.is_err() {
-                            print
-                                .history
-                                .print(self.theme.error, r.unwrap_err().to_string());
-                        }
-                    }
-                }
-                '[' => {
-                    self.decrease_print_width();
-                }
-                ']' => {
-                    self.increase_print_width();
-                }
-                'p' => {
-                    self.next_display();
-                }
-                'P' => {
-                    self.prev_display();
-                }
-                'o' => {
-                    self.next_element();
-                }
-                'O' => {
-                    self.prev_element();
-                }
-                'i' => {
-                    self.next_mode();
-                }
-                'I' => {
-                    self.prev_mode();
-                }
-                'n' => {
-                    self.next_hit(modifier);
-                }
-                'N' => {
-                    self.prev_hit(modifier);
-                }
-                _ => {}
-            }
+    fn on_key(&mut self, c: char) {
+        let display = self.tabs.current().display;
+        if self.is_text_insert() {
+            self.handle_text_insert(c);
+        } else if display == Display::Element && (c.is_ascii_hexdigit() || c == '.') {
+            self.handle_insert(c);
+        } else if c == 'u' {
+            self.do_undo();
+            self.seed_insert_vector();
+        } else if c == 'U' {
+            self.do_redo();
+            self.seed_insert_vector();
+        } else if c == 'v' {
+            self.toggle_selection();
         }
     }
 
-    fn parse_u64_number(input: &str) -> Result<u64, ParseIntError> {
-        let z;
-        if input.starts_with("0x") {
-            z = u64::from_str_radix(input.strip_prefix("0x").unwrap(), 16);
-        } else if input.ends_with("o") {
-            z = u64::from_str_radix(input.strip_suffix("o").unwrap(), 8);
-        } else if input.ends_with("b") {
-            z = u64::from_str_radix(input.strip_suffix("b").unwrap(), 2);
-        } else {
-            z = input.parse::<u64>();
+    fn toggle_selection(&mut self) {
+        if self.tabs.current().display != Display::Element {
+            return;
         }
-        z
+        let anchor = match self.tabs.current().anchor {
+            Some(_) => None,
+            None => self.cursor_pos(),
+        };
+        self.tabs.current().anchor = anchor;
     }
 
-    pub fn pin_tab(&mut self) {
-        if !self.tabs.tabs.is_empty() {
-            self.tabs.tabs[self.tabs.index].fileitem_index = self.files.index;
+    // [start, end) from the anchor to the element under the cursor, both included
+    pub fn selection(&mut self) -> Option<(u64, u64)> {
+        if !self.is_insert_mode() || self.tabs.current().display != Display::Element {
+            return None;
         }
+        let anchor = self.tabs.current().anchor?;
+        let cursor = self.cursor_pos()?;
+        let size = element_display_size(self.tabs.current().element_display) as u64;
+        Some((
+            std::cmp::min(anchor, cursor),
+            std::cmp::max(anchor, cursor) + size,
+        ))
     }
 
-    pub fn on_command(&mut self, print: &mut Print<'a>) {
-        let inputs: Vec<&str> = (self.textarea.lines()[0]).split_whitespace().collect();
-        if inputs.len() > 1 {
-            if inputs[0].eq("file") {
-                if inputs[1].eq("next") {
-                    self.files.next();
-                    self.pin_tab();
-                } else if inputs[1].eq("prev") {
+    // args start with <start> <end> followed by rest more, without them the selection is used
+    fn range_args<'b>(
+        &mut self,
+        print: &mut Print,
+        name: &str,
+        args: &[&'b str],
+        rest: usize,
+    ) -> Option<(u64, u64, Vec<&'b str>)> {
+        if args.len() >= rest + 2 {
+            let (start, end) = self.parse_range(print, args[0], args[1])?;
+            Some((start, end, args[2..].to_vec()))
+        } else if args.len() < rest {
+            self.report_command(print, name);
+            None
+        } else if let Some((start, end)) = self.selection() {
+            Some((start, end, args.to_vec()))
+        } else {
+            print.history.print(
+                self.theme.error,
+                format!("{} needs <start> <end> or a selection", name),
+            );
+            None
+        }
+    }
+
+    fn seed_insert_vector(&mut self) {
+        if self.files.files.is_empty() || !self.is_insert_mode() {
+            return;
+        }
+        let pos = self.tabs.cursor_pos();
+        let fi = self.files.current(self.tabs.file_index());
+        let ti = self.tabs.current();
+        if ti.display != Display::Element {
+            return;
+        }
+        Files::do_apply_patch(&mut fi.block, &fi.patch);
+        let size = element_display_size(ti.element_display) as usize;
+        let width = Tabs::element_input_size(ti) as usize;
+        let mut bytes = [0u8; 16];
+        if let Some(src) = fi.block.buffer.get(pos..pos + size) {
+            bytes[..size].copy_from_slice(src);
+        }
+        let value = u128::from_le_bytes(bytes);
+        let digits = match ti.element_mode {
+            ElementMode::Hex => format!("{:0width$x}", value, width = width),
+            ElementMode::Dec => format!("{:0width$}", value, width = width),
+            ElementMode::Oct => format!("{:0width$o}", value, width = width),
+            ElementMode::Bin | ElementMode::Bit => format!("{:0width$b}", value, width = width),
+        };
+        ti.insert_vector = Self::pop(digits.as_bytes());
+        ti.insert_index = 0;
+    }
+
+    fn toggle_asm_bytes(&mut self) {
+        if !self.tabs.tabs.is_empty() {
+            let ti = self.tabs.current();
+            ti.show_asm_bytes = !ti.show_asm_bytes;
+        }
+    }
+
+    fn toggle_squeeze(&mut self) {
+        if !self.tabs.tabs.is_empty() {
+            let ti = self.tabs.current();
+            ti.squeeze = !ti.squeeze;
+        }
+    }
+
+    fn toggle_ruler(&mut self) {
+        if !self.tabs.tabs.is_empty() {
+            let ti = self.tabs.current();
+            ti.ruler = !ti.ruler;
+        }
+    }
+
+    fn next_print_gutter(&mut self) {
+        if !self.tabs.tabs.is_empty() {
+            let ti = self.tabs.current();
+            ti.print_gutter = match ti.print_gutter {
+                PrintGutter::Off => PrintGutter::Offset,
+                PrintGutter::Offset => PrintGutter::Line,
+                PrintGutter::Line => PrintGutter::Off,
+            };
+        }
+    }
+
+    fn toggle_byte_classes(&mut self) {
+        if !self.tabs.tabs.is_empty() {
+            let ti = self.tabs.current();
+            ti.byte_classes = !ti.byte_classes;
+        }
+    }
+
+    fn toggle_split(&mut self) {
+        if !self.tabs.tabs.is_empty() {
+            let ti = self.tabs.current();
+            ti.split = !ti.split;
+        }
+    }
+
+    fn next_split_display(&mut self) {
+        if !self.tabs.tabs.is_empty() {
+            let ti = &mut self.tabs.tabs[self.tabs.index];
+            ti.split_display = DISPLAYS
+                [(DISPLAYS[ti.split_display as usize] as usize + 1).rem_euclid(DISPLAYS.len())];
+        }
+    }
+
+    pub fn on_draw_split(&mut self) -> &Vec<Spans<'a>> {
+        let ti = self.tabs.current();
+        let display = ti.display;
+        ti.display = ti.split_display;
+        self.on_draw();
+        self.tabs.current().display = display;
+        &self.cache.buffer
+    }
+
+    // absolute offset under the cursor in insert mode, else start of the view
+    pub fn cursor_pos(&mut self) -> Option<u64> {
+        if self.files.files.is_empty() || self.tabs.tabs.is_empty() {
+            return None;
+        }
+        let ti = self.tabs.current();
+        let (insert_mode, display, row, column) =
+            (ti.insert_mode, ti.display, ti.cursor_row, ti.cursor_column);
+        let size = element_display_size(ti.element_display) as usize;
+        let row_size = size * std::cmp::max((ti.print_width + size - 1) / size, 1);
+        let offset = self.files.current(self.tabs.file_index()).block.offset;
+        if !insert_mode {
+            return Some(offset);
+        }
+        match display {
+            Display::Element => Some(offset + (row as usize * row_size + column as usize) as u64),
+            Display::Asm => self.cache.asm.get(row as usize).map(|line| line.offset),
+            Display::Print if self.is_text_insert() => {
+                Some(offset + self.text_cursor().unwrap_or(0) as u64)
+            }
+            _ => Some(offset),
+        }
+    }
+
+    fn copy_offset(&mut self, print: &mut Print) {
+        let offset = match self.cursor_pos() {
+            Some(offset) => format!("0x{:x}", offset),
+            None => return,
+        };
+        match self.clipboard.copy(offset.clone()) {
+            Ok(()) => print
+                .history
+                .print(self.theme.text, format!("Copied {}", offset)),
+            Err(e) => print.history.print(
+                self.theme.text,
+                format!("Offset {} (clipboard unavailable: {})", offset, e),
+            ),
+        }
+    }
+
+    fn follow_jump(&mut self, print: &mut Print) {
+        if self.files.files.is_empty() || self.tabs.tabs.is_empty() {
+            return;
+        }
+        if self.tabs.current().display != Display::Asm {
+            return;
+        }
+        let row = if self.is_insert_mode() {
+            self.tabs.current().cursor_row as usize
+        } else {
+            0
+        };
+        let line = self.cache.asm.get(row).cloned();
+        if line.is_none() || line.as_ref().unwrap().target.is_none() {
+            print
+                .history
+                .print(self.theme.error, "No branch target at cursor".to_string());
+            return;
+        }
+        let target = line.unwrap().target.unwrap();
+        let fi = self.files.current(self.tabs.file_index());
+        fi.jumps.push(fi.block.offset);
+        fi.block.offset = target;
+        self.tabs.current().cursor_row = 0;
+        print
+            .history
+            .print(self.theme.text, format!("Followed jump to 0x{:x}", target));
+    }
+
+    fn nop_instruction(&mut self, print: &mut Print) {
+        if self.files.files.is_empty() || self.tabs.tabs.is_empty() {
+            return;
+        }
+        if self.tabs.current().display != Display::Asm {
+            print
+                .history
+                .print(self.theme.error, "nop needs the asm view".to_string());
+            return;
+        }
+        if ![AsmArch::X86, AsmArch::X86_64].contains(&self.tabs.current().asm_arch) {
+            print
+                .history
+                .print(self.theme.error, "nop needs an x86 asm arch".to_string());
+            return;
+        }
+        let row = if self.is_insert_mode() {
+            self.tabs.current().cursor_row as usize
+        } else {
+            0
+        };
+        let line = match self.cache.asm.get(row).cloned() {
+            Some(line) => line,
+            None => {
+                print
+                    .history
+                    .print(self.theme.error, "No instruction at cursor".to_string());
+                return;
+            }
+        };
+        let end = line.offset + line.len as u64;
+        let done = self.patch_range(print, line.offset, end, |_, bytes| bytes.fill(0x90));
+        if done > 0 {
+            print.history.print(
+                self.theme.text,
+                format!("nop {} bytes at 0x{:08x}", done, line.offset),
+            );
+        }
+    }
+
+    fn asm_stats(&mut self, print: &mut Print) {
+        if self.tabs.tabs.is_empty() || self.tabs.current().display != Display::Asm {
+            print
+                .history
+                .print(self.theme.error, "asm stats needs the asm view".to_string());
+            return;
+        }
+        let count = self.cache.asm.len();
+        let total: usize = self.cache.asm.iter().map(|line| line.len).sum();
+        let invalid = self.cache.asm.iter().filter(|line| line.invalid).count();
+        let average = if count > 0 {
+            total as f64 / count as f64
+        } else {
+            0.0
+        };
+        print.history.print(
+            self.theme.text,
+            format!(
+                "{} instructions, average length {:.2}, {} invalid",
+                count, average, invalid
+            ),
+        );
+    }
+
+    // branches and memory operands in the decoded block that point at the offset
+    fn handle_xref(&mut self, print: &mut Print, input: Option<&str>) {
+        if self.tabs.tabs.is_empty() || self.tabs.current().display != Display::Asm {
+            print
+                .history
+                .print(self.theme.error, "xref needs the asm view".to_string());
+            return;
+        }
+        let target = match input {
+            Some(input) => self.parse_number_arg(print, "offset", input),
+            None => self.cursor_pos(),
+        };
+        let target = match target {
+            Some(target) => target,
+            None => return,
+        };
+        let mut hits = Hits::new(format!("xref 0x{:x}", target), 1);
+        for line in self.cache.asm.iter() {
+            if line.target == Some(target) || line.mem_target == Some(target) {
+                hits.hits.push(line.offset);
+            }
+        }
+        if hits.hits.is_empty() {
+            print.history.print(
+                self.theme.text,
+                format!("No references to 0x{:x} in this block", target),
+            );
+            return;
+        }
+        for offset in hits.hits.iter() {
+            print.history.print(
+                self.theme.text,
+                format!("0x{:x} refers to 0x{:x}", offset, target),
+            );
+        }
+        let flag = hits.flag.clone();
+        let fi = self.files.current(self.tabs.file_index());
+        fi.hhits.add(hits);
+        // so n and N walk the references right away
+        if let Some(index) = fi.hhits.hits.iter().position(|h| h.flag == flag) {
+            fi.hhits.selected = index;
+        }
+    }
+
+    fn jump_back(&mut self, print: &mut Print) {
+        if self.files.files.is_empty() || self.tabs.tabs.is_empty() {
+            return;
+        }
+        let fi = self.files.current(self.tabs.file_index());
+        if let Some(offset) = fi.jumps.pop() {
+            fi.block.offset = offset;
+            print
+                .history
+                .print(self.theme.text, format!("Returned to 0x{:x}", offset));
+        }
+    }
+
+    fn on_write(&mut self, print: &mut Print) {
+        if !self.tabs.tabs.is_empty() {
+            let summary = match self.files.write(self.tabs.file_index()) {
+                Ok(summary) => summary,
+                Err(e) => {
+                    print.history.print(self.theme.error, e.to_string());
+                    return;
+                }
+            };
+            if summary.bytes == 0 {
+                print
+                    .history
+                    .print(self.theme.text, "No changes to write".to_string());
+                return;
+            }
+            let mut ranges: Vec<String> = summary
+                .ranges
+                .iter()
+                .take(8)
+                .map(|(start, end)| format!("0x{:x}-0x{:x}", start, end))
+                .collect();
+            if summary.ranges.len() > 8 {
+                ranges.push("...".to_string());
+            }
+            print.history.print(
+                self.theme.text,
+                format!(
+                    "Wrote {} bytes from {} edits: {}",
+                    summary.bytes,
+                    summary.entries,
+                    ranges.join(", ")
+                ),
+            );
+            if self.verify_writes {
+                self.verify_write(print, &summary);
+            }
+        }
+    }
+
+    fn verify_write(&mut self, print: &mut Print, summary: &WriteSummary) {
+        match self.files.verify(self.tabs.file_index(), summary) {
+            Ok(mismatches) if mismatches.is_empty() => {
+                print
+                    .history
+                    .print(self.theme.text, format!("Verified {} bytes", summary.bytes));
+            }
+            Ok(mismatches) => {
+                let mut ranges: Vec<String> = mismatches
+                    .iter()
+                    .take(8)
+                    .map(|(start, end)| format!("0x{:x}-0x{:x}", start, end))
+                    .collect();
+                if mismatches.len() > 8 {
+                    ranges.push("...".to_string());
+                }
+                print.history.print(
+                    self.theme.error,
+                    format!("Verify failed, file differs at: {}", ranges.join(", ")),
+                );
+            }
+            Err(e) => {
+                print
+                    .history
+                    .print(self.theme.error, format!("Verify failed: {}", e));
+            }
+        }
+    }
+
+    fn on_action(&mut self, print: &mut Print<'a>, action: Action) {
+        match action {
+            Action::Help => self.on_f1(print),
+            Action::Prompt => self.enter_prompt = true,
+            Action::Quit => self.should_quit = true,
+            Action::Write => self.on_write(print),
+            Action::DecreaseWidth => self.decrease_print_width(),
+            Action::IncreaseWidth => self.increase_print_width(),
+            Action::NextDisplay => self.next_display(),
+            Action::PrevDisplay => self.prev_display(),
+            Action::NextElement => {
+                self.next_element();
+                self.snap_offset();
+            }
+            Action::PrevElement => {
+                self.prev_element();
+                self.snap_offset();
+            }
+            Action::NextMode => self.next_mode(),
+            Action::PrevMode => self.prev_mode(),
+            Action::NextHit => self.next_hit(print, false, 1),
+            Action::PrevHit => self.prev_hit(print, false, 1),
+            Action::NextHitGroup => self.next_hit(print, true, 1),
+            Action::PrevHitGroup => self.prev_hit(print, true, 1),
+            Action::NextTheme => self.next_theme(),
+            Action::ToggleHistory => self.on_tab(print),
+            Action::Up => self.on_up(print),
+            Action::Down => self.on_down(print),
+            Action::Left => self.on_left(print),
+            Action::Right => self.on_right(print),
+            Action::PageUp => self.on_pageup(print),
+            Action::PageDown => self.on_pagedown(print),
+            Action::Home => self.on_home(print),
+            Action::End => self.on_end(print),
+            Action::Insert => self.on_insert(print),
+            Action::FollowJump => self.follow_jump(print),
+            Action::JumpBack => self.jump_back(print),
+            Action::ToggleAsmBytes => self.toggle_asm_bytes(),
+            Action::ToggleSqueeze => self.toggle_squeeze(),
+            Action::ToggleSplit => self.toggle_split(),
+            Action::ToggleByteClasses => self.toggle_byte_classes(),
+            Action::CopyOffset => self.copy_offset(print),
+            Action::ToggleRuler => self.toggle_ruler(),
+            Action::NextPrintGutter => self.next_print_gutter(),
+            Action::RepeatCommand => self.repeat_command(print),
+            Action::StepForward => self.on_step(true),
+            Action::StepBackward => self.on_step(false),
+            Action::ToggleHitGroups => self.show_hits = !self.show_hits,
+            Action::RemoveHitGroup => self.remove_hit_group(print),
+            Action::NextSplitDisplay => self.next_split_display(),
+        }
+    }
+
+    pub fn parse_u64_number(input: &str) -> Result<u64, ParseIntError> {
+        let z;
+        if input.starts_with("0x") {
+            z = u64::from_str_radix(input.strip_prefix("0x").unwrap(), 16);
+        } else if input.ends_with("o") {
+            z = u64::from_str_radix(input.strip_suffix("o").unwrap(), 8);
+        } else if input.ends_with("b") {
+            z = u64::from_str_radix(input.strip_suffix("b").unwrap(), 2);
+        } else {
+            z = input.parse::<u64>();
+        }
+        z
+    }
+
+    fn handle_session(&mut self, print: &mut Print, cmd: String, path: String) {
+        if cmd.eq("save") {
+            let ret = Session::new(&self.files, &self.tabs).save(&path);
+            if ret.is_err() {
+                print.history.print(
+                    self.theme.error,
+                    format!("Failed to save session: {}", ret.unwrap_err()),
+                );
+            } else {
+                print
+                    .history
+                    .print(self.theme.text, format!("Session saved to {}", path));
+            }
+        } else if cmd.eq("load") {
+            match Session::load(&path) {
+                Ok(session) => {
+                    let undo_limit = self.files.undo_limit;
+                    self.files = session.files;
+                    self.files.set_undo_limit(undo_limit);
+                    for fi in self.files.files.iter_mut() {
+                        Files::split_patch(&mut fi.patch);
+                    }
+                    self.tabs = session.tabs;
+                    self.synced_tab = None;
+                    if self.files.index >= self.files.files.len() {
+                        self.files.index = 0;
+                    }
+                    if self.tabs.index >= self.tabs.tabs.len() {
+                        self.tabs.index = 0;
+                    }
+                    for tab in self.tabs.tabs.iter_mut() {
+                        if tab.fileitem_index >= self.files.files.len() {
+                            tab.fileitem_index = 0;
+                        }
+                        if tab.block_size == 0 {
+                            tab.block_size = match self.files.files.get(tab.fileitem_index) {
+                                Some(fi) => fi.block.size,
+                                None => self.files.block_size,
+                            };
+                        }
+                    }
+                    for index in 0..self.files.files.len() {
+                        self.detect_file_type(index);
+                    }
+                    print
+                        .history
+                        .print(self.theme.text, format!("Session loaded from {}", path));
+                }
+                Err(err) => {
+                    print
+                        .history
+                        .print(self.theme.error, format!("Failed to load session: {}", err));
+                }
+            }
+        }
+    }
+
+    pub fn add_file(&mut self, print: &mut Print, path: String) {
+        let ret = std::fs::File::open(&path);
+        if ret.is_err() {
+            print.history.print(
+                self.theme.error,
+                format!("Failed to open {}: {}", path, ret.unwrap_err()),
+            );
+        } else {
+            self.files.add(path, &mut self.tabs);
+            let index = self.files.files.len() - 1;
+            self.detect_file_type(index);
+            if let Some(name) = self.at.clone() {
+                self.open_at(print, index, &name);
+            }
+        }
+    }
+
+    // moves a newly added file to the section or symbol given with --at
+    fn open_at(&mut self, print: &mut Print, index: usize, name: &str) {
+        let fi = self.files.current(index);
+        let resolved = match fi.kind {
+            Some("ELF") => Elf::parse(&fi.path).map(|elf| elf.resolve(name)),
+            Some("PE") => Pe::parse(&fi.path).map(|pe| pe.resolve(name)),
+            _ => {
+                print.history.print(
+                    self.theme.error,
+                    format!("{}: --at needs an ELF or PE file", fi.path),
+                );
+                return;
+            }
+        };
+        match resolved {
+            Ok(Some(offset)) => {
+                fi.block.offset = offset;
+                if let Some(tab) = self.tabs.tabs.last_mut() {
+                    tab.offset = offset;
+                }
+                print.history.print(
+                    self.theme.text,
+                    format!("{}: {} at offset 0x{:x}", fi.path, name, offset),
+                );
+            }
+            Ok(None) => print.history.print(
+                self.theme.error,
+                format!("{}: unknown section or symbol {}", fi.path, name),
+            ),
+            Err(err) => print.history.print(
+                self.theme.error,
+                format!("{}: parse failed: {}", fi.path, err),
+            ),
+        }
+    }
+
+    fn handle_recent(&mut self, print: &mut Print, which: Option<&str>) {
+        let recent = recent::load();
+        match which {
+            None => {
+                if recent.is_empty() {
+                    print
+                        .history
+                        .print(self.theme.text, "No recent files".to_string());
+                }
+                for (i, path) in recent.iter().enumerate() {
+                    print
+                        .history
+                        .print(self.theme.text, format!("{:>4} {}", i + 1, path));
+                }
+            }
+            Some(which) => match self.parse_number_arg(print, "recent index", which) {
+                Some(n) if n >= 1 && n <= recent.len() as u64 => {
+                    self.add_file(print, recent[n as usize - 1].clone());
+                    if !self.tabs.tabs.is_empty() {
+                        self.tabs.index = self.tabs.tabs.len() - 1;
+                    }
+                }
+                Some(n) => print.history.print(
+                    self.theme.error,
+                    format!("recent index {} out of range 1..{}", n, recent.len()),
+                ),
+                None => {}
+            },
+        }
+    }
+
+    fn handle_bookmark(&mut self, print: &mut Print, cmd: Option<&str>, name: Option<&str>) {
+        if self.files.files.is_empty() || self.tabs.tabs.is_empty() {
+            return;
+        }
+        let cmd = match cmd {
+            Some(cmd) => cmd,
+            None => {
+                let fi = self.files.current(self.tabs.file_index());
+                if fi.bookmarks.is_empty() {
+                    print
+                        .history
+                        .print(self.theme.text, "No bookmarks".to_string());
+                }
+                for (offset, name) in fi.bookmarks.iter() {
+                    print
+                        .history
+                        .print(self.theme.text, format!("0x{:08x} {}", offset, name));
+                }
+                return;
+            }
+        };
+        if !self.check_keyword(print, "bookmark command", cmd, &["add", "go", "del"]) {
+            return;
+        }
+        let name = match name {
+            Some(name) => name,
+            None => {
+                print
+                    .history
+                    .print(self.theme.error, format!("bookmark {} needs a name", cmd));
+                return;
+            }
+        };
+        let pos = self.cursor_pos().unwrap_or(0);
+        let theme = self.theme;
+        let fi = self.files.current(self.tabs.file_index());
+        let found = fi
+            .bookmarks
+            .iter()
+            .find(|(_, n)| n.as_str() == name)
+            .map(|(offset, _)| *offset);
+        match (cmd, found) {
+            ("add", _) => {
+                if let Some(offset) = found {
+                    fi.bookmarks.remove(&offset);
+                }
+                fi.bookmarks.insert(pos, name.to_string());
+                print
+                    .history
+                    .print(theme.text, format!("Bookmark {} at 0x{:x}", name, pos));
+            }
+            ("go", Some(offset)) => {
+                fi.jumps.push(fi.block.offset);
+                fi.block.offset = offset;
+                self.tabs.current().cursor_row = 0;
+                print.history.print(
+                    theme.text,
+                    format!("Went to bookmark {} at 0x{:x}", name, offset),
+                );
+            }
+            ("del", Some(offset)) => {
+                fi.bookmarks.remove(&offset);
+                print
+                    .history
+                    .print(theme.text, format!("Deleted bookmark {}", name));
+            }
+            _ => print
+                .history
+                .print(theme.error, format!("No bookmark named {}", name)),
+        }
+    }
+
+    fn save_log(&mut self, print: &mut Print, path: String) {
+        match print.history.save(&path) {
+            Ok(lines) => print.history.print(
+                self.theme.text,
+                format!("Saved {} lines to {}", lines, path),
+            ),
+            Err(e) => print
+                .history
+                .print(self.theme.error, format!("Saving log failed: {}", e)),
+        }
+    }
+
+    fn current_file(&self) -> Option<&File> {
+        let tab = self.tabs.tabs.get(self.tabs.index)?;
+        self.files.files.get(tab.fileitem_index)
+    }
+
+    // bytes of the active tab's block as last read with patches applied, without the
+    // padding past the end of file, empty when no file is open
+    pub fn current_bytes(&self) -> &[u8] {
+        self.current_file().map_or(&[], |fi| fi.block.data())
+    }
+
+    pub fn current_offset(&self) -> u64 {
+        self.current_file().map_or(0, |fi| fi.block.offset)
+    }
+
+    // bytes from the cursor up to the end of file, at most size
+    fn bytes_at_cursor(&mut self, size: usize) -> Option<(u64, Vec<u8>)> {
+        let pos = self.cursor_pos()?;
+        let start = pos.checked_sub(self.current_offset())? as usize;
+        let bytes = self.current_bytes();
+        let end = std::cmp::min(start + size, bytes.len());
+        Some((pos, bytes.get(start..end)?.to_vec()))
+    }
+
+    fn handle_inspect(&mut self, print: &mut Print, endian: Option<&str>) {
+        let little_endian = match endian {
+            None => true,
+            Some(endian) => {
+                if !self.check_keyword(print, "endianness", endian, &["le", "be"]) {
+                    return;
+                }
+                endian.eq("le")
+            }
+        };
+        let (pos, bytes) = match self.bytes_at_cursor(16) {
+            Some(at) => at,
+            None => return,
+        };
+        print.history.print(
+            self.theme.text,
+            format!(
+                "0x{:08x} as {}",
+                pos,
+                if little_endian { "LE" } else { "BE" }
+            ),
+        );
+        for (name, value) in inspect::timestamps(&bytes, little_endian) {
+            print.history.print(
+                self.theme.text,
+                format!(
+                    "{:>10} {}",
+                    name,
+                    value.unwrap_or_else(|| "out of range".to_string())
+                ),
+            );
+        }
+        if let Ok(b) = <[u8; 16]>::try_from(bytes.as_slice()) {
+            print.history.print(
+                self.theme.text,
+                format!("{:>10} {}", "guid", inspect::guid(&b)),
+            );
+            print.history.print(
+                self.theme.text,
+                format!("{:>10} {}", "uuid", inspect::uuid(&b)),
+            );
+        }
+    }
+
+    fn detect_file_type(&mut self, index: usize) {
+        let fi = self.files.current(index);
+        fi.kind = sniff(&fi.path).unwrap_or(None);
+    }
+
+    fn report_file_type(&mut self, print: &mut Print) {
+        if !self.files.files.is_empty() && !self.tabs.tabs.is_empty() {
+            let index = self.tabs.file_index();
+            self.detect_file_type(index);
+            let fi = self.files.current(index);
+            print.history.print(
+                self.theme.text,
+                format!("{}: {}", fi.path, fi.kind.unwrap_or("unknown")),
+            );
+        }
+    }
+
+    fn handle_elf(&mut self, print: &mut Print, cmd: Option<&str>) {
+        if self.files.files.is_empty() || self.tabs.tabs.is_empty() {
+            return;
+        }
+        let index = self.tabs.file_index();
+        let ret = Elf::parse(&self.files.current(index).path);
+        if ret.is_err() {
+            print.history.print(
+                self.theme.error,
+                format!("ELF parse failed: {}", ret.err().unwrap()),
+            );
+            return;
+        }
+        let elf = ret.unwrap();
+        if cmd.eq(&Some("entry")) {
+            match elf.vaddr_to_offset(elf.entry) {
+                Some(offset) => {
+                    self.files.current(index).block.offset = offset;
+                    print.history.print(
+                        self.theme.text,
+                        format!("Entry point 0x{:x} at offset 0x{:x}", elf.entry, offset),
+                    );
+                }
+                None => print.history.print(
+                    self.theme.error,
+                    format!("Entry point 0x{:x} is not mapped from file", elf.entry),
+                ),
+            }
+            return;
+        }
+        print.history.print(
+            self.theme.header,
+            format!(
+                "ELF{} {} machine 0x{:x} entry 0x{:x}",
+                if elf.class64 { 64 } else { 32 },
+                if elf.little_endian { "LE" } else { "BE" },
+                elf.machine,
+                elf.entry
+            ),
+        );
+        for (i, segment) in elf.segments.iter().enumerate() {
+            print.history.print(
+                self.theme.text,
+                format!(
+                    "segment {:3} type 0x{:08x} flags 0x{:x} offset 0x{:08x} vaddr 0x{:016x} filesz 0x{:x} memsz 0x{:x}",
+                    i,
+                    segment.kind,
+                    segment.flags,
+                    segment.offset,
+                    segment.vaddr,
+                    segment.filesz,
+                    segment.memsz
+                ),
+            );
+        }
+        for (i, section) in elf.sections.iter().enumerate() {
+            print.history.print(
+                self.theme.text,
+                format!(
+                    "section {:3} {:<20} type 0x{:08x} offset 0x{:08x} addr 0x{:016x} size 0x{:x}",
+                    i, section.name, section.kind, section.offset, section.addr, section.size
+                ),
+            );
+        }
+    }
+
+    fn handle_pe(&mut self, print: &mut Print, cmd: Option<&str>) {
+        if self.files.files.is_empty() || self.tabs.tabs.is_empty() {
+            return;
+        }
+        let index = self.tabs.file_index();
+        let ret = Pe::parse(&self.files.current(index).path);
+        if ret.is_err() {
+            print.history.print(
+                self.theme.error,
+                format!("PE parse failed: {}", ret.err().unwrap()),
+            );
+            return;
+        }
+        let pe = ret.unwrap();
+        self.files.current(index).image_base = pe.image_base;
+        if cmd.eq(&Some("entry")) {
+            match pe.rva_to_offset(pe.entry) {
+                Some(offset) => {
+                    self.files.current(index).block.offset = offset;
+                    print.history.print(
+                        self.theme.text,
+                        format!("Entry point RVA 0x{:x} at offset 0x{:x}", pe.entry, offset),
+                    );
+                }
+                None => print.history.print(
+                    self.theme.error,
+                    format!("Entry point RVA 0x{:x} is not mapped from file", pe.entry),
+                ),
+            }
+            return;
+        }
+        print.history.print(
+            self.theme.header,
+            format!(
+                "{} machine 0x{:x} entry RVA 0x{:x} image base 0x{:x}",
+                if pe.pe32_plus { "PE32+" } else { "PE32" },
+                pe.machine,
+                pe.entry,
+                pe.image_base
+            ),
+        );
+        for (i, section) in pe.sections.iter().enumerate() {
+            print.history.print(
+                self.theme.text,
+                format!(
+                    "section {:3} {:<8} offset 0x{:08x} raw size 0x{:x} RVA 0x{:08x} virtual size 0x{:x}",
+                    i,
+                    section.name,
+                    section.raw_offset,
+                    section.raw_size,
+                    section.virtual_address,
+                    section.virtual_size
+                ),
+            );
+        }
+    }
+
+    pub fn tab_title(&self, index: usize) -> String {
+        let tab = &self.tabs.tabs[index];
+        let kind = self
+            .files
+            .files
+            .get(tab.fileitem_index)
+            .and_then(|fi| fi.kind);
+        if let Some(kind) = kind {
+            format!("{} [{}]", tab.title, kind)
+        } else {
+            tab.title.clone()
+        }
+    }
+
+    pub fn pin_tab(&mut self) {
+        if !self.tabs.tabs.is_empty() {
+            self.tabs.tabs[self.tabs.index].fileitem_index = self.files.index;
+        }
+    }
+
+    pub fn on_command(&mut self, print: &mut Print<'a>) {
+        let line = self.textarea.lines()[0].clone();
+        if !line.trim().is_empty() {
+            self.last_command = Some(line.clone());
+        }
+        self.run_command(print, line);
+    }
+
+    fn repeat_command(&mut self, print: &mut Print<'a>) {
+        match self.last_command.clone() {
+            Some(line) => self.run_command(print, line),
+            None => print
+                .history
+                .print(self.theme.error, "No command to repeat".to_string()),
+        }
+    }
+
+    fn run_command(&mut self, print: &mut Print<'a>, line: String) {
+        let inputs: Vec<&str> = line.split_whitespace().collect();
+        if inputs.len() == 1 && inputs[0].eq("filetype") {
+            self.report_file_type(print);
+        } else if inputs.len() == 1 && inputs[0].eq("nop") {
+            self.nop_instruction(print);
+        } else if inputs.len() == 1 && inputs[0].eq("changes") {
+            self.handle_changes(print);
+        } else if !inputs.is_empty() && inputs[0].eq("xref") {
+            self.handle_xref(print, inputs.get(1).copied());
+        } else if !inputs.is_empty() && inputs[0].eq("elf") {
+            self.handle_elf(print, inputs.get(1).copied());
+        } else if !inputs.is_empty() && inputs[0].eq("inspect") {
+            self.handle_inspect(print, inputs.get(1).copied());
+        } else if !inputs.is_empty() && inputs[0].eq("recent") {
+            self.handle_recent(print, inputs.get(1).copied());
+        } else if !inputs.is_empty() && inputs[0].eq("pe") {
+            self.handle_pe(print, inputs.get(1).copied());
+        } else if !inputs.is_empty() && inputs[0].eq("bookmark") {
+            self.handle_bookmark(print, inputs.get(1).copied(), inputs.get(2).copied());
+        } else if inputs.len() > 1 {
+            if inputs[0].eq("file") {
+                if inputs[1].eq("next") {
+                    self.files.next();
+                    self.pin_tab();
+                } else if inputs[1].eq("prev") {
                     self.files.previous();
                     self.pin_tab();
                 } else if inputs[1].eq("add") && inputs.len() > 2 {
-                    self.files.add(inputs[2].to_string(), &mut self.tabs);
+                    for path in &inputs[2..] {
+                        self.add_file(print, path.to_string());
+                    }
+                } else {
+                    self.check_keyword(print, "file command", inputs[1], &["next", "prev", "add"]);
                 }
             } else if inputs[0].eq("tab") {
                 if inputs[1].eq("next") {
                     self.tabs.next();
                 } else if inputs[1].eq("prev") {
                     self.tabs.previous();
+                } else if inputs[1].eq("clone") {
+                    if !self.tabs.tabs.is_empty() {
+                        self.tabs.clone_current();
+                    }
+                } else {
+                    self.check_keyword(print, "tab command", inputs[1], &["next", "prev", "clone"]);
+                }
+            } else if inputs[0].eq("search") && inputs[1].eq("count") && inputs.len() > 2 {
+                match self.scan_hits(Self::rest_of(&line, 2)) {
+                    Ok(hits) => print.history.print(
+                        self.theme.text,
+                        format!("Found {} results for {}", hits.hits.len(), hits.flag),
+                    ),
+                    Err(err) => print
+                        .history
+                        .print(self.theme.error, format!("Search failed: {}", err)),
                 }
             } else if inputs[0].eq("search") {
-                let ret = self.handle_search(inputs[1].to_string());
+                let ret = self.handle_search(Self::rest_of(&line, 1));
                 if ret.is_err() {
-                    print
-                        .history
-                        .print(self.theme.error, "Search failed!".to_string());
+                    print.history.print(
+                        self.theme.error,
+                        format!("Search failed: {}", ret.unwrap_err()),
+                    );
                 } else {
                     print.history.print(
                         self.theme.text,
@@ -1339,21 +3942,154 @@ impl<'a> App<'a> {
                     );
                 }
             } else if inputs[0].eq("block_size") {
-                self.set_block_size(Self::parse_u64_number(inputs[1]));
+                self.set_block_size(print, inputs[1]);
             } else if inputs[0].eq("offset") {
-                self.set_block_offset(Self::parse_u64_number(inputs[1]));
+                self.set_block_offset(print, inputs[1]);
+            } else if inputs[0].eq("cursor") {
+                self.set_cursor(print, inputs[1]);
+            } else if inputs[0].eq("cursors") && inputs[1].eq("clear") {
+                if !self.tabs.tabs.is_empty() {
+                    self.tabs.current().cursors.clear();
+                }
+            } else if inputs[0].eq("cursors") && inputs.len() > 3 {
+                self.set_cursors(print, &inputs);
             } else if inputs[0].eq("print") {
                 if inputs.len() > 2 {
-                    self.handle_print(print, inputs[1].to_string(), inputs[2].to_string());
+                    self.handle_print(
+                        print,
+                        inputs[1].to_string(),
+                        inputs[2].to_string(),
+                        inputs.get(3).copied(),
+                    );
+                } else {
+                    self.report_command(print, inputs[0]);
                 }
             } else if inputs[0].eq("show") {
                 if inputs.len() > 2 {
-                    self.handle_show(inputs[1].to_string(), inputs[2].to_string());
+                    self.handle_show(print, inputs[1].to_string(), inputs[2].to_string());
+                    self.snap_offset();
+                } else {
+                    self.report_command(print, inputs[0]);
+                }
+            } else if inputs[0].eq("session") {
+                if inputs.len() > 2 {
+                    self.handle_session(print, inputs[1].to_string(), inputs[2].to_string());
+                } else {
+                    self.report_command(print, inputs[0]);
                 }
+            } else if inputs[0].eq("theme") {
+                self.set_theme(print, inputs[1].to_string());
+            } else if ["xor", "add", "sub", "rol", "ror"].contains(&inputs[0]) {
+                self.handle_transform(print, &inputs);
+            } else if inputs[0].eq("carve") {
+                self.handle_carve(print, &inputs);
+            } else if inputs[0].eq("decode") {
+                self.handle_decode(print, &inputs);
+            } else if inputs[0].eq("bswap") {
+                self.handle_bswap(print, &inputs);
+            } else if inputs[0].eq("asm") && inputs[1].eq("stats") {
+                self.asm_stats(print);
+            } else if inputs[0].eq("asm") && inputs.len() > 2 {
+                self.set_asm_option(print, inputs[1], inputs[2]);
+            } else if inputs[0].eq("hit") {
+                self.goto_hit(print, inputs[1]);
+            } else if inputs[0].eq("wrap") {
+                self.set_wrap(print, inputs[1].to_string());
+            } else if inputs[0].eq("align") {
+                if inputs[1].eq("up") && inputs.len() > 2 {
+                    self.align_offset(print, true, inputs[2]);
+                } else {
+                    self.align_offset(print, false, inputs[1]);
+                }
+            } else if inputs[0].eq("tail") {
+                self.set_tail(print, inputs[1]);
+            } else if inputs[0].eq("watch") {
+                self.set_watch(print, inputs[1].to_string());
+            } else if inputs[0].eq("step") {
+                self.set_step(print, inputs[1]);
+            } else if inputs[0].eq("log") {
+                if inputs[1].eq("save") && inputs.len() > 2 {
+                    self.save_log(print, Self::rest_of(&line, 2));
+                } else if self.check_keyword(print, "log command", inputs[1], &["save"]) {
+                    self.report_command(print, inputs[0]);
+                }
+            } else if inputs[0].eq("relbase") {
+                self.set_rel_base(print, inputs[1]);
+            } else if inputs[0].eq("tabwidth") {
+                self.set_tab_width(print, inputs[1]);
+            } else if inputs[0].eq("undolimit") {
+                self.set_undo_limit(print, inputs[1]);
+            } else if inputs[0].eq("placeholder") {
+                self.set_placeholder(print, inputs[1]);
+            } else if inputs[0].eq("rows") {
+                self.set_rows(print, inputs[1]);
+            } else if inputs[0].eq("snap") {
+                self.set_snap(print, inputs[1].to_string());
+            } else {
+                self.report_command(print, inputs[0]);
+            }
+        } else if !inputs.is_empty() {
+            self.report_command(print, inputs[0]);
+        }
+    }
+
+    fn rest_of(line: &str, skip: usize) -> String {
+        let mut rest = line.trim_start();
+        for _ in 0..skip {
+            rest = rest
+                .trim_start_matches(|c: char| !c.is_whitespace())
+                .trim_start();
+        }
+        rest.trim_end().to_string()
+    }
+
+    fn report_command(&self, print: &mut Print, name: &str) {
+        if self.check_keyword(print, "command", name, COMMANDS) {
+            print.history.print(
+                self.theme.error,
+                format!("missing or invalid arguments for command: {}", name),
+            );
+        }
+    }
+
+    fn set_wrap(&mut self, print: &mut Print, value: String) {
+        if !self.tabs.tabs.is_empty() {
+            if value.eq("on") {
+                self.tabs.current().wrap_navigation = true;
+            } else if value.eq("off") {
+                self.tabs.current().wrap_navigation = false;
+            } else {
+                self.check_keyword(print, "value", &value, &["on", "off"]);
+            }
+        }
+    }
+
+    fn set_snap(&mut self, print: &mut Print, value: String) {
+        if !self.tabs.tabs.is_empty() {
+            if value.eq("on") {
+                self.tabs.current().snap_elements = true;
+                self.snap_offset();
+            } else if value.eq("off") {
+                self.tabs.current().snap_elements = false;
+            } else {
+                self.check_keyword(print, "value", &value, &["on", "off"]);
             }
         }
     }
 
+    fn snap_offset(&mut self) {
+        if self.files.files.is_empty() || self.tabs.tabs.is_empty() {
+            return;
+        }
+        let ti = self.tabs.current();
+        if !ti.snap_elements || ti.display != Display::Element {
+            return;
+        }
+        let size = element_display_size(ti.element_display) as u64;
+        let fi = self.files.current(self.tabs.file_index());
+        fi.block.offset -= fi.block.offset % size;
+    }
+
     pub fn on_draw(&mut self) -> &Vec<Spans<'a>> {
         if self.tabs.current().display == Display::Asm {
             self.get_asm();
@@ -1375,7 +4111,9 @@ impl<'a> App<'a> {
                     self.get_decbyte();
                 } else if self.tabs.current().element_mode == ElementMode::Oct {
                     self.get_octbyte();
-                } else if self.tabs.current().element_mode == ElementMode::Bin {
+                } else if self.tabs.current().element_mode == ElementMode::Bin
+                    || self.tabs.current().element_mode == ElementMode::Bit
+                {
                     self.get_binbyte();
                 }
             } else if self.tabs.current().element_display == ElementDisplay::Word {
@@ -1385,7 +4123,9 @@ impl<'a> App<'a> {
                     self.get_decword();
                 } else if self.tabs.current().element_mode == ElementMode::Oct {
                     self.get_octword();
-                } else if self.tabs.current().element_mode == ElementMode::Bin {
+                } else if self.tabs.current().element_mode == ElementMode::Bin
+                    || self.tabs.current().element_mode == ElementMode::Bit
+                {
                     self.get_binword();
                 }
             } else if self.tabs.current().element_display == ElementDisplay::DWord {
@@ -1395,7 +4135,9 @@ impl<'a> App<'a> {
                     self.get_decdword();
                 } else if self.tabs.current().element_mode == ElementMode::Oct {
                     self.get_octdword();
-                } else if self.tabs.current().element_mode == ElementMode::Bin {
+                } else if self.tabs.current().element_mode == ElementMode::Bin
+                    || self.tabs.current().element_mode == ElementMode::Bit
+                {
                     self.get_bindword();
                 }
             } else if self.tabs.current().element_display == ElementDisplay::QWord {
@@ -1405,9 +4147,23 @@ impl<'a> App<'a> {
                     self.get_decqword();
                 } else if self.tabs.current().element_mode == ElementMode::Oct {
                     self.get_octqword();
-                } else if self.tabs.current().element_mode == ElementMode::Bin {
+                } else if self.tabs.current().element_mode == ElementMode::Bin
+                    || self.tabs.current().element_mode == ElementMode::Bit
+                {
                     self.get_binqword();
                 }
+            } else if self.tabs.current().element_display == ElementDisplay::OWord {
+                if self.tabs.current().element_mode == ElementMode::Hex {
+                    self.get_hexoword();
+                } else if self.tabs.current().element_mode == ElementMode::Dec {
+                    self.get_decoword();
+                } else if self.tabs.current().element_mode == ElementMode::Oct {
+                    self.get_octoword();
+                } else if self.tabs.current().element_mode == ElementMode::Bin
+                    || self.tabs.current().element_mode == ElementMode::Bit
+                {
+                    self.get_binoword();
+                }
             }
         } else if self.tabs.current().display == Display::Visual {
             if self.tabs.current().visual_display == VisualDisplay::Color {
@@ -1419,13 +4175,98 @@ impl<'a> App<'a> {
         &self.cache.buffer
     }
 
+    pub fn hit_status(&mut self) -> Option<String> {
+        if self.files.files.is_empty() || self.tabs.tabs.is_empty() {
+            return None;
+        }
+        let fi = self.files.current(self.tabs.file_index());
+        let hits = fi.hhits.hits.get(fi.hhits.selected)?;
+        if hits.hits.is_empty() {
+            return None;
+        }
+        Some(format!(
+            "hit {} of {} [{}]",
+            hits.selected + 1,
+            hits.hits.len(),
+            hits.flag
+        ))
+    }
+
+    // start and length of the shown block as fractions of the file size
+    pub fn scroll_position(&mut self) -> Option<(f64, f64)> {
+        if self.files.files.is_empty() || self.tabs.tabs.is_empty() {
+            return None;
+        }
+        let fi = self.files.current(self.tabs.file_index());
+        if fi.size == 0 {
+            return Some((0.0, 1.0));
+        }
+        let size = fi.size as f64;
+        let start = (fi.block.offset as f64 / size).min(1.0);
+        let length = (fi.block.size as f64 / size).min(1.0 - start);
+        Some((start, length))
+    }
+
+    pub fn status_line(&mut self) -> Option<String> {
+        if self.files.files.is_empty() || self.tabs.tabs.is_empty() {
+            return None;
+        }
+        let ti = self.tabs.tabs[self.tabs.index].clone();
+        let fi = self.files.current(ti.fileitem_index);
+        let view = match ti.display {
+            Display::Element => format!("{:?} {:?}", ti.element_display, ti.element_mode),
+            Display::Asm => format!("Asm {:?}", ti.asm_display),
+            Display::Print => format!("{:?}", ti.print_display),
+            Display::Visual => format!("Visual {:?}", ti.visual_display),
+        };
+        let percent = (std::cmp::min(fi.block.offset, fi.size) * 100)
+            .checked_div(fi.size)
+            .unwrap_or(0);
+        Some(format!(
+            "{}{}  0x{:08x} / 0x{:08x} {:>3}%  {}  {}{}",
+            fi.path,
+            if fi.patch.is_empty() { "" } else { " [+]" },
+            fi.block.offset,
+            fi.size,
+            percent,
+            view,
+            match (ti.insert_mode, ti.cursors.len()) {
+                (false, _) => "VIEW".to_string(),
+                (true, 0) | (true, 1) => "INSERT".to_string(),
+                (true, n) => format!("INSERT x{}", n),
+            },
+            if self.repeat_count > 0 {
+                format!("  {}", self.repeat_count)
+            } else {
+                String::new()
+            }
+        ))
+    }
+
     pub fn sync_file(&mut self, print: &mut Print) {
+        if !self.files.files.is_empty() && !self.tabs.tabs.is_empty() {
+            let index = self.tabs.index;
+            let ti = &mut self.tabs.tabs[index];
+            let fi = self.files.current(ti.fileitem_index);
+            fi.block.size = ti.block_size;
+            // the block is shared by all tabs on a file, so after a switch the tab's offset wins
+            if self.synced_tab == Some(index) {
+                ti.offset = fi.block.offset;
+            } else {
+                fi.block.offset = ti.offset;
+            }
+            self.synced_tab = Some(index);
+        }
         if Self::need_block(self) {
             let ret = Self::read_block(self);
             if ret.is_err() {
-                print
-                    .history
-                    .print(self.theme.error, "Failed to read block!".to_string());
+                print.history.print(
+                    self.theme.error,
+                    format!("Failed to read block: {}", ret.unwrap_err()),
+                );
+                let fi = self.files.current(self.tabs.file_index());
+                fi.block.prev_offset = fi.block.offset;
+                fi.block.prev_size = fi.block.size;
             }
         }
         if !self.files.files.is_empty() {
@@ -1443,32 +4284,221 @@ impl<'a> App<'a> {
                 self.textarea.input(key);
             }
         } else {
+            if !self.tabs.tabs.is_empty() {
+                self.tabs.current().insert_error = false;
+            }
+            let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+            match key.code {
+                _ if self.show_history && self.handle_history_key(print, key) => {}
+                KeyCode::Char('z') if ctrl && self.is_text_insert() => self.do_undo(),
+                KeyCode::Char('y') if ctrl && self.is_text_insert() => self.do_redo(),
+                KeyCode::Char(c) if self.is_insert_mode() => self.on_key(c),
+                KeyCode::Char(c @ '0'..='9')
+                    if (c != '0' || self.repeat_count > 0) && self.keys.get(&key).is_none() =>
+                {
+                    self.push_repeat_digit(c);
+                }
+                _ => {
+                    let count = std::mem::take(&mut self.repeat_count);
+                    if let Some(action) = self.keys.get(&key) {
+                        let times = if REPEATABLE_ACTIONS.contains(&action) {
+                            std::cmp::max(count, 1)
+                        } else {
+                            1
+                        };
+                        self.repeat_action(print, action, times);
+                        self.seed_insert_vector();
+                        self.check_tail(print);
+                    }
+                }
+            }
+        }
+    }
+
+    fn repeat_action(&mut self, print: &mut Print<'a>, action: Action, times: usize) {
+        match action {
+            Action::NextHit => self.next_hit(print, false, times),
+            Action::PrevHit => self.prev_hit(print, false, times),
+            Action::NextHitGroup => self.next_hit(print, true, times),
+            Action::PrevHitGroup => self.prev_hit(print, true, times),
+            _ => {
+                for _ in 0..times {
+                    self.on_action(print, action);
+                }
+            }
+        }
+    }
+
+    fn push_repeat_digit(&mut self, c: char) {
+        let digit = c.to_digit(10).unwrap_or(0) as usize;
+        self.repeat_count = std::cmp::min(self.repeat_count * 10 + digit, MAX_REPEAT_COUNT);
+    }
+
+    fn handle_history_key(&mut self, print: &mut Print, key: KeyEvent) -> bool {
+        let history = &mut print.history;
+        if history.searching {
             match key.code {
-                KeyCode::Char(c) => self.on_key(print, c, key.modifiers),
-                KeyCode::Left => self.on_left(print),
-                KeyCode::Up => self.on_up(print),
-                KeyCode::Right => self.on_right(print),
-                KeyCode::Down => self.on_down(print),
-                KeyCode::PageUp => self.on_pageup(print),
-                KeyCode::PageDown => self.on_pagedown(print),
-                KeyCode::Tab => self.on_tab(print),
-                KeyCode::End => self.on_end(print),
-                KeyCode::Home => self.on_home(print),
-                KeyCode::Insert => self.on_insert(print),
-                KeyCode::F(1) => self.on_f1(print),
+                KeyCode::Char(c) => history.query.push(c),
+                KeyCode::Backspace => {
+                    history.query.pop();
+                }
+                KeyCode::Enter => {
+                    history.searching = false;
+                    history.find(true, true);
+                }
+                KeyCode::Esc => {
+                    history.searching = false;
+                    history.query.clear();
+                    history.found = true;
+                }
                 _ => {}
             }
+            return true;
         }
+        if !(key.modifiers - KeyModifiers::SHIFT).is_empty() {
+            return false;
+        }
+        match key.code {
+            KeyCode::Char('/') => {
+                history.searching = true;
+                history.query.clear();
+                history.found = true;
+            }
+            KeyCode::Char('n') if !history.query.is_empty() => {
+                history.find(true, false);
+            }
+            KeyCode::Char('N') if !history.query.is_empty() => {
+                history.find(false, false);
+            }
+            _ => return false,
+        }
+        true
     }
 
-    pub fn on_tick(&mut self) {
+    pub fn on_tick(&mut self, print: &mut Print) {
         let now = Instant::now();
         self.progress = now.duration_since(self.now).as_secs_f64();
         self.now = now;
+        self.check_watched(print);
+    }
+
+    fn set_watch(&mut self, print: &mut Print, value: String) {
+        if self.files.files.is_empty() || self.tabs.tabs.is_empty() {
+            return;
+        }
+        let fi = self.files.current(self.tabs.file_index());
+        if fi.is_memory() {
+            print.history.print(
+                self.theme.error,
+                format!("{} is an in-memory buffer", fi.path),
+            );
+        } else if value.eq("on") {
+            fi.watch = true;
+            fi.stamp = Files::stamp(&fi.path).ok();
+        } else if value.eq("off") {
+            fi.watch = false;
+            fi.stamp = None;
+            if fi.tail.take().is_some() {
+                print
+                    .history
+                    .print(self.theme.text, format!("Left tail mode for {}", fi.path));
+            }
+        } else {
+            self.check_keyword(print, "value", &value, &["on", "off"]);
+        }
+    }
+
+    fn set_tail(&mut self, print: &mut Print, value: &str) {
+        if self.files.files.is_empty() || self.tabs.tabs.is_empty() {
+            return;
+        }
+        if !self.check_keyword(print, "value", value, &["on", "off"]) {
+            return;
+        }
+        let ti = self.tabs.current();
+        let (pw, ph) = (ti.print_width, ti.print_height);
+        let fi = self.files.current(self.tabs.file_index());
+        if fi.is_memory() {
+            print.history.print(
+                self.theme.error,
+                format!("{} is an in-memory buffer", fi.path),
+            );
+        } else if value.eq("on") {
+            if !fi.watch {
+                fi.watch = true;
+                fi.stamp = Files::stamp(&fi.path).ok();
+            }
+            let offset = Self::max_offset(fi.size, pw, ph);
+            fi.block.offset = offset;
+            fi.tail = Some(offset);
+            print
+                .history
+                .print(self.theme.text, format!("Tailing {}", fi.path));
+        } else if fi.tail.take().is_some() {
+            print
+                .history
+                .print(self.theme.text, format!("Left tail mode for {}", fi.path));
+        }
+    }
+
+    // scrolling away from where tail mode put the view ends tail mode
+    fn check_tail(&mut self, print: &mut Print) {
+        if self.files.files.is_empty() || self.tabs.tabs.is_empty() {
+            return;
+        }
+        let fi = self.files.current(self.tabs.file_index());
+        if fi.tail.is_some() && fi.tail != Some(fi.block.offset) {
+            fi.tail = None;
+            print
+                .history
+                .print(self.theme.text, format!("Left tail mode for {}", fi.path));
+        }
+    }
+
+    fn check_watched(&mut self, print: &mut Print) {
+        let (pw, ph) = match self.tabs.tabs.get(self.tabs.index) {
+            Some(ti) => (ti.print_width, ti.print_height),
+            None => return,
+        };
+        self.check_tail(print);
+        for fi in self.files.files.iter_mut().filter(|fi| fi.watch) {
+            let stamp = match Files::stamp(&fi.path) {
+                Ok(stamp) => stamp,
+                Err(_) => continue,
+            };
+            if fi.stamp.is_none() {
+                fi.stamp = Some(stamp);
+            }
+            if fi.stamp == Some(stamp) {
+                continue;
+            }
+            let len = stamp.0;
+            fi.stamp = Some(stamp);
+            fi.size = len;
+            fi.block.prev_size = 0;
+            if fi.tail.is_some() || fi.block.offset > len {
+                fi.block.offset = Self::max_offset(len, pw, ph);
+            }
+            if fi.tail.is_some() {
+                fi.tail = Some(fi.block.offset);
+            }
+            let stale = fi.patch.range(len..).count();
+            fi.patch.retain(|offset, _| *offset < len);
+            print.history.print(
+                self.theme.text,
+                format!("Reloaded {} ({} bytes)", fi.path, len),
+            );
+            if stale > 0 {
+                print.history.print(
+                    self.theme.error,
+                    format!("Dropped {} edits past end of {}", stale, fi.path),
+                );
+            }
+        }
     }
 
-    fn pop(input: &[u8]) -> [u8; 64] {
-        let mut array = [0u8; 64];
+    fn pop(input: &[u8]) -> [u8; INSERT_VECTOR_SIZE] {
+        let mut array = [0u8; INSERT_VECTOR_SIZE];
         for (&x, p) in input.iter().zip(array.iter_mut()) {
             *p = x;
         }
@@ -1476,8 +4506,22 @@ impl<'a> App<'a> {
     }
 
     pub fn get_help(&mut self) -> Vec<Spans<'a>> {
-        let text;
-        if self.is_insert_mode() {
+        let mut text;
+        if self.is_text_insert() {
+            text = vec![
+                Spans::from("Help"),
+                Spans::from("tab       toggle history log"),
+                Spans::from("up        move cursor to the line above"),
+                Spans::from("down      move cursor to the line below"),
+                Spans::from("left      move cursor left"),
+                Spans::from("right     move cursor right"),
+                Spans::from("<char>    overwrite byte with the typed character"),
+                Spans::from("ctrl+z    undo"),
+                Spans::from("ctrl+y    redo"),
+                Spans::from("home      jump cursor to start of page"),
+                Spans::from("insert    exit insert mode"),
+            ];
+        } else if self.is_insert_mode() {
             text = vec![
                 Spans::from("Help"),
                 Spans::from("tab       toggle history log"),
@@ -1487,40 +4531,1135 @@ impl<'a> App<'a> {
                 Spans::from("right     move cursor right"),
                 Spans::from("<0-fF>    edit nibbles"),
                 Spans::from("'.'       skip nibble"),
+                Spans::from("<0-7>     flip bit in bit mode"),
                 Spans::from("u         undo"),
                 Spans::from("U         redo"),
+                Spans::from("v         start or clear selection"),
                 Spans::from("home      jump cursor to start of page"),
                 Spans::from("end       jump cursor to end of page"),
+                Spans::from("enter     follow branch target in asm view"),
                 Spans::from("insert    exit insert mode"),
             ];
         } else {
-            text = vec![
-                Spans::from("Help"),
-                Spans::from("':'       enter command line"),
-                Spans::from("Q         exit"),
-                Spans::from("W         save changes to selected file"),
-                Spans::from("[         decrease print width"),
-                Spans::from("]         increase print width"),
-                Spans::from("p         next display mode"),
-                Spans::from("P         prev display mode"),
-                Spans::from("o         next element display mode"),
-                Spans::from("O         prev element display mode"),
-                Spans::from("i         next interpretation mode"),
-                Spans::from("I         prev interpretation mode"),
-                Spans::from("n         jump to next search hit"),
-                Spans::from("N         jump to prev search hit"),
-                Spans::from("Ctrl+n    pick next group of search hits"),
-                Spans::from("Ctrl+N    pick prev group of search hits"),
-                Spans::from("tab       toggle history log"),
-                Spans::from("up        scroll up"),
-                Spans::from("down      scroll down"),
-                Spans::from("pageup    scroll page up"),
-                Spans::from("pagedown  scroll page down"),
-                Spans::from("home      jump to start of file"),
-                Spans::from("end       jump to end of file"),
-                Spans::from("insert    enter insert mode"),
-            ];
+            text = vec![Spans::from("Help")];
+            for (action, _, description) in ACTIONS {
+                let keys = self.keys.keys_of(*action);
+                if !keys.is_empty() {
+                    text.push(Spans::from(format!(
+                        "{:<16} {}",
+                        keys.join(","),
+                        description
+                    )));
+                }
+            }
         }
         text
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scratch::Scratch;
+
+    // a 16 byte wide view over an in-memory file, read like the first draw would
+    fn memory_app(data: Vec<u8>) -> App<'static> {
+        let mut app = App::new("test", Vec::new());
+        app.files
+            .add_memory("memory".to_string(), data, &mut app.tabs);
+        app.tabs.current().print_height = 4;
+        app.files.current(0).block.size = 64;
+        app.read_block().unwrap();
+        app
+    }
+
+    fn insert_at(app: &mut App, row: u16, column: u16) {
+        let ti = app.tabs.current();
+        ti.insert_mode = true;
+        ti.insert_index = 0;
+        ti.insert_error = false;
+        ti.cursor_row = row;
+        ti.cursor_column = column;
+        app.seed_insert_vector();
+    }
+
+    #[test]
+    fn edits_stop_at_end_of_file() {
+        // 20 bytes end the second row of the view 4 bytes in, the rest is padding
+        let mut app = memory_app((0u8..20).collect());
+        assert_eq!(app.current_bytes().len(), 20);
+        insert_at(&mut app, 1, 3);
+        app.handle_insert('a');
+        assert!(!app.tabs.current().insert_error);
+        assert_eq!(app.files.current(0).patch.get(&19), Some(&vec![0xa3]));
+        insert_at(&mut app, 1, 4);
+        app.handle_insert('a');
+        assert!(app.tabs.current().insert_error);
+        assert_eq!(app.tabs.current().insert_index, 0);
+        assert_eq!(app.files.current(0).patch.len(), 1);
+        app.tabs.current().element_mode = ElementMode::Bit;
+        insert_at(&mut app, 1, 4);
+        app.handle_insert('0');
+        assert!(app.tabs.current().insert_error);
+        assert_eq!(app.files.current(0).patch.len(), 1);
+        app.do_undo();
+        app.do_undo();
+        assert!(app.files.current(0).patch.is_empty());
+    }
+
+    fn command<'a>(app: &mut App<'a>, print: &mut Print<'a>, line: &str) {
+        app.textarea = TextArea::from([line.to_string()]);
+        app.on_command(print);
+    }
+
+    #[test]
+    fn stride_cursors_repeat_edits() {
+        let mut app = memory_app(vec![0u8; 64]);
+        let mut print = Print::default();
+        insert_at(&mut app, 0, 0);
+        command(&mut app, &mut print, "cursors 0 0x10 3");
+        assert_eq!(app.tabs.current().cursors, [0, 0x10, 0x20]);
+        app.handle_insert('7');
+        app.tabs.current().element_mode = ElementMode::Bit;
+        app.handle_insert('7');
+        for offset in [0, 0x10, 0x20] {
+            assert_eq!(app.files.current(0).patch.get(&offset), Some(&vec![0xf0]));
+        }
+        app.do_undo();
+        for offset in [0, 0x10, 0x20] {
+            assert_eq!(app.files.current(0).patch.get(&offset), Some(&vec![0x70]));
+        }
+        app.do_undo();
+        assert!(app.files.current(0).patch.is_empty());
+    }
+
+    fn press<'a>(app: &mut App<'a>, print: &mut Print<'a>, keys: &str) {
+        for c in keys.chars() {
+            app.handle_input(print, KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+        }
+    }
+
+    #[test]
+    fn count_prefix_moves_hits_at_once() {
+        let mut app = memory_app(vec![0u8; 64]);
+        let mut print = Print::default();
+        let mut hits = Hits::new("test".to_string(), 1);
+        hits.hits = vec![0, 8, 16, 24, 32];
+        app.files.current(0).hhits.add(hits);
+        let logged = print.history.history.len();
+        press(&mut app, &mut print, "3n");
+        let fi = app.files.current(0);
+        assert_eq!(fi.hhits.hits[0].selected, 3);
+        assert_eq!(fi.block.offset, 24);
+        assert_eq!(print.history.history.len(), logged + 1);
+        press(&mut app, &mut print, "99999n");
+        assert_eq!(app.files.current(0).hhits.hits[0].selected, 2);
+        assert_eq!(print.history.history.len(), logged + 2);
+        app.repeat_action(&mut print, Action::PrevHit, 3);
+        assert_eq!(app.files.current(0).hhits.hits[0].selected, 4);
+        assert_eq!(app.repeat_count, 0);
+    }
+
+    #[test]
+    fn hits_are_logged_with_context() {
+        let mut app = memory_app((0u8..32).collect());
+        let mut print = Print::default();
+        let mut hits = Hits::new("x".to_string(), 2);
+        hits.hits = vec![1, 0x10, 0x1e];
+        app.files.current(0).hhits.add(hits);
+        press(&mut app, &mut print, "n");
+        assert_eq!(
+            last_line(&print),
+            "hit 2 of 3 at 0x00000010 for x: 0c 0d 0e 0f [10 11] 12 13 14 15"
+        );
+        // context is cut at the ends of the file
+        press(&mut app, &mut print, "n");
+        assert_eq!(
+            last_line(&print),
+            "hit 3 of 3 at 0x0000001e for x: 1a 1b 1c 1d [1e 1f]"
+        );
+        press(&mut app, &mut print, "n");
+        assert_eq!(
+            last_line(&print),
+            "hit 1 of 3 at 0x00000001 for x: 00 [01 02] 03 04 05 06"
+        );
+    }
+
+    #[test]
+    fn range_edits_undo_as_one_group() {
+        // three patch entries wide, each one pushed separately
+        let size = 3 * MAX_PATCH_SIZE as usize;
+        let mut app = memory_app(vec![0u8; size]);
+        let mut print = Print::default();
+        command(&mut app, &mut print, &format!("xor 0 {} ff", size));
+        assert_eq!(app.files.current(0).patch.len(), 3);
+        command(&mut app, &mut print, "xor 0 2 0f");
+        app.do_undo();
+        let patch = &app.files.current(0).patch;
+        assert!(patch.values().all(|v| v.iter().all(|b| *b == 0xff)));
+        app.do_undo();
+        let patch = &app.files.current(0).patch;
+        assert!(patch.values().all(|v| v.iter().all(|b| *b == 0)));
+        app.do_redo();
+        let patch = &app.files.current(0).patch;
+        assert_eq!(patch.values().map(|v| v.len()).sum::<usize>(), size);
+        assert!(patch.values().all(|v| v.iter().all(|b| *b == 0xff)));
+    }
+
+    #[test]
+    fn batch_edits_undo_as_one_group() {
+        let mut app = memory_app(vec![0u8; 64]);
+        let mut print = Print::default();
+        insert_at(&mut app, 0, 0);
+        command(&mut app, &mut print, "cursors 0 8 4");
+        app.handle_insert('1');
+        app.handle_insert('2');
+        app.do_undo();
+        let patch = &app.files.current(0).patch;
+        assert_eq!(patch.len(), 4);
+        assert!(patch.values().all(|v| v == &vec![0x10]));
+        app.do_undo();
+        assert!(app.files.current(0).patch.is_empty());
+    }
+
+    #[test]
+    fn new_edit_drops_redo() {
+        let mut app = memory_app(vec![0u8; 64]);
+        let mut print = Print::default();
+        command(&mut app, &mut print, "xor 0 8 ff");
+        app.do_undo();
+        command(&mut app, &mut print, "xor 16 18 0f");
+        let patch = app.files.current(0).patch.clone();
+        app.do_redo();
+        assert_eq!(app.files.current(0).patch, patch);
+        app.do_undo();
+        app.do_redo();
+        assert_eq!(app.files.current(0).patch, patch);
+    }
+
+    #[test]
+    fn entropy_ignores_padding_of_short_block() {
+        let mut block = Block::new(2048);
+        let mut file = Cursor::new(vec![0x41u8; 10]);
+        let read = Files::read_block(&mut file, 2048, 0, 10, &mut block.buffer).unwrap();
+        assert_eq!(read, 10);
+        assert_eq!(block.buffer[10], 0xff);
+        assert_eq!(App::calc_entropy(&block, read), 0.0);
+        let mut file = Cursor::new((0u8..10).collect::<Vec<u8>>());
+        let read = Files::read_block(&mut file, 2048, 0, 10, &mut block.buffer).unwrap();
+        assert!((App::calc_entropy(&block, read) - 10f64.log2()).abs() < 1e-9);
+        assert_eq!(App::calc_entropy(&block, 0), 0.0);
+    }
+
+    fn last_line(print: &Print) -> String {
+        match print.history.history.back() {
+            Some(line) => line.0.iter().map(|span| span.content.as_ref()).collect(),
+            None => String::new(),
+        }
+    }
+
+    #[test]
+    fn missing_files_report_errors() {
+        let mut app = App::new("test", Vec::new());
+        let mut print = Print::default();
+        app.add_file(&mut print, Scratch::new("missing").path());
+        assert!(last_line(&print).starts_with("Failed to open"));
+        assert!(app.files.files.is_empty() && app.tabs.tabs.is_empty());
+
+        let file = Scratch::with("deleted", vec![0u8; 4096]);
+        app.add_file(&mut print, file.path());
+        app.sync_file(&mut print);
+        std::fs::remove_file(&file).unwrap();
+        app.files.current(0).block.offset = 2048;
+        app.sync_file(&mut print);
+        assert!(last_line(&print).starts_with("Failed to read block"));
+        assert!(app.get_entropy().is_empty());
+        assert!(app.handle_search("00".to_string()).is_err());
+    }
+
+    fn asm_app(data: Vec<u8>) -> (App<'static>, Print<'static>) {
+        let mut app = memory_app(data);
+        let mut print = Print::default();
+        app.tabs.current().display = Display::Asm;
+        app.sync_file(&mut print);
+        (app, print)
+    }
+
+    fn line_text(line: &Spans) -> String {
+        line.0.iter().map(|span| span.content.as_ref()).collect()
+    }
+
+    #[test]
+    fn asm_follows_edits() {
+        let (mut app, mut print) = asm_app(vec![0x90, 0x90, 0xc3]);
+        assert!(line_text(&app.get_asm()[0]).ends_with("nop"));
+        command(&mut app, &mut print, "xor 0 1 53");
+        app.sync_file(&mut print);
+        let lines = app.get_asm().clone();
+        assert!(line_text(&lines[0]).ends_with("ret"));
+        assert!(line_text(&lines[1]).ends_with("nop"));
+        app.do_undo();
+        app.sync_file(&mut print);
+        assert!(line_text(&app.get_asm()[0]).ends_with("nop"));
+    }
+
+    #[test]
+    fn invalid_instructions_are_styled() {
+        let (mut app, _print) = asm_app(vec![0, 0, 0xff, 0xff, 0, 0]);
+        let invalid = app.theme.invalid;
+        let lines = app.get_asm().clone();
+        assert_eq!(lines.len(), 3);
+        assert!(line_text(&lines[0]).ends_with("[rax],al"));
+        assert!(lines[0].0.iter().all(|span| span.style != invalid));
+        assert!(line_text(&lines[1]).ends_with("(bad)"));
+        assert!(lines[1].0.iter().all(|span| span.style == invalid));
+        assert!(lines[2].0.iter().all(|span| span.style != invalid));
+        assert!(app.cache.asm[1].invalid);
+    }
+
+    #[test]
+    fn asm_options_change_the_output() {
+        // mov eax,0abcd1234h
+        let (mut app, mut print) = asm_app(vec![0xb8, 0x34, 0x12, 0xcd, 0xab]);
+        assert!(line_text(&app.get_asm()[0]).ends_with("eax,0`ABCD`1234h"));
+        command(&mut app, &mut print, "asm separator off");
+        app.sync_file(&mut print);
+        assert!(line_text(&app.get_asm()[0]).ends_with("eax,0ABCD1234h"));
+        command(&mut app, &mut print, "asm uppercase off");
+        command(&mut app, &mut print, "asm space on");
+        app.sync_file(&mut print);
+        assert!(line_text(&app.get_asm()[0]).ends_with("eax, 0abcd1234h"));
+        command(&mut app, &mut print, "asm separator maybe");
+        assert!(last_line(&print).contains("maybe"));
+        assert!(!app.asm_options.digit_separator);
+    }
+
+    #[test]
+    fn parses_patterns() {
+        assert_eq!(App::parse_pattern("MZ").unwrap(), b"MZ");
+        assert_eq!(
+            App::parse_pattern("\"PE\\x00\\0\\xfF\\t\\\"\\\\\"").unwrap(),
+            b"PE\x00\x00\xff\t\"\\"
+        );
+        assert_eq!(App::parse_pattern("\"\u{e9}\"").unwrap(), [0xc3, 0xa9]);
+        assert_eq!(App::parse_pattern("\"").unwrap(), b"\"");
+        for bad in ["", "\"\"", "\"\\x4\"", "\"\\xzz\"", "\"\\q\"", "\"a\\\""] {
+            assert!(App::parse_pattern(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn element_switches_snap_the_offset() {
+        let mut app = memory_app(vec![0u8; 256]);
+        let mut print = Print::default();
+        app.tabs.current().display = Display::Element;
+        command(&mut app, &mut print, "offset 0x1d");
+        app.on_action(&mut print, Action::NextElement);
+        assert_eq!(app.current_offset(), 0x1c);
+        app.on_action(&mut print, Action::NextElement);
+        assert_eq!(app.current_offset(), 0x1c);
+        app.on_action(&mut print, Action::NextElement);
+        assert_eq!(app.current_offset(), 0x18);
+        app.on_action(&mut print, Action::NextElement);
+        assert_eq!(app.current_offset(), 0x10);
+        command(&mut app, &mut print, "snap maybe");
+        assert!(last_line(&print).contains("maybe"));
+        assert!(app.tabs.current().snap_elements);
+        command(&mut app, &mut print, "snap off");
+        command(&mut app, &mut print, "offset 0x1d");
+        app.on_action(&mut print, Action::PrevElement);
+        assert_eq!(app.current_offset(), 0x1d);
+    }
+
+    #[test]
+    fn step_moves_by_the_set_amount() {
+        // 100 bytes in a 16x4 view, the last full view starts at 0x30
+        let mut app = memory_app(vec![0u8; 100]);
+        let mut print = Print::default();
+        command(&mut app, &mut print, "step 0x14");
+        press(&mut app, &mut print, ">>");
+        assert_eq!(app.current_offset(), 0x28);
+        press(&mut app, &mut print, ">");
+        assert_eq!(app.current_offset(), 0x30);
+        press(&mut app, &mut print, "<");
+        assert_eq!(app.current_offset(), 0x1c);
+        press(&mut app, &mut print, "<<");
+        assert_eq!(app.current_offset(), 0);
+        command(&mut app, &mut print, "step 0");
+        assert!(last_line(&print).contains("step must be above 0"));
+        press(&mut app, &mut print, ">");
+        assert_eq!(app.current_offset(), 0x14);
+    }
+
+    #[test]
+    fn align_rounds_to_power_of_two() {
+        let mut app = memory_app(vec![0u8; 0x3000]);
+        let mut print = Print::default();
+        command(&mut app, &mut print, "offset 0x1234");
+        command(&mut app, &mut print, "align 0x1000");
+        assert_eq!(app.current_offset(), 0x1000);
+        assert!(last_line(&print).contains("0x00001000"));
+        command(&mut app, &mut print, "align 0x1000");
+        assert_eq!(app.current_offset(), 0x1000);
+        command(&mut app, &mut print, "align up 0x1000");
+        assert_eq!(app.current_offset(), 0x1000);
+        command(&mut app, &mut print, "offset 0x1001");
+        command(&mut app, &mut print, "align up 0x1000");
+        assert_eq!(app.current_offset(), 0x2000);
+        for bad in ["0", "3", "0x1800"] {
+            command(&mut app, &mut print, &format!("align {}", bad));
+            assert!(last_line(&print).contains("power of two"), "{}", bad);
+            assert_eq!(app.current_offset(), 0x2000);
+        }
+    }
+
+    #[test]
+    fn element_ascii_gutter_follows_grid() {
+        let mut app = memory_app((b'A'..b'A' + 18).collect());
+        app.tabs.current().print_width = 4;
+        app.tabs.current().print_height = 5;
+        insert_at(&mut app, 1, 2);
+        let current = app.theme.current_text;
+        let lines = app.get_hexword().clone();
+        assert_eq!(line_text(&lines[2]), "0x00000004  4645 4847  EFGH");
+        let marked: Vec<String> = lines[2]
+            .0
+            .iter()
+            .filter(|span| span.style == current)
+            .map(|span| span.content.to_string())
+            .collect();
+        assert_eq!(marked, [" 4847", "G", "H"]);
+        // the short last row keeps its ascii column under the rows above
+        assert_eq!(line_text(&lines[5]), "0x00000010  5251       QR  ");
+    }
+
+    #[test]
+    fn offsets_are_relative_to_base() {
+        assert_eq!(rel_offset(0x1234, 0), "0x00001234");
+        assert_eq!(rel_offset(0x1234, 0x1000), " 0x00000234");
+        assert_eq!(rel_offset(0x1000, 0x1000), " 0x00000000");
+        assert_eq!(rel_offset(0x0ff0, 0x1000), "-0x00000010");
+
+        let mut app = memory_app(vec![0u8; 64]);
+        let mut print = Print::default();
+        command(&mut app, &mut print, "relbase 0x20");
+        let lines = app.get_hexbyte().clone();
+        assert!(line_text(&lines[1]).starts_with("-0x00000020"));
+        assert!(line_text(&lines[3]).starts_with(" 0x00000000"));
+    }
+
+    #[test]
+    fn cursor_jumps_to_an_offset() {
+        // dword rows of 16 bytes, a page is 0x40 bytes
+        let mut app = memory_app(vec![0u8; 256]);
+        let mut print = Print::default();
+        app.tabs.current().display = Display::Asm;
+        command(&mut app, &mut print, "cursor 0x26");
+        assert!(last_line(&print).contains("element view"));
+        assert!(!app.tabs.current().insert_mode);
+        command(&mut app, &mut print, "show dword hex");
+        let position = |app: &mut App| {
+            let ti = app.tabs.current();
+            (ti.cursor_row, ti.cursor_column)
+        };
+        command(&mut app, &mut print, "cursor 0x26");
+        assert!(app.tabs.current().insert_mode);
+        assert_eq!(app.current_offset(), 0);
+        assert_eq!(position(&mut app), (2, 4));
+        command(&mut app, &mut print, "cursor 0x95");
+        assert_eq!(app.current_offset(), 0x90);
+        assert_eq!(position(&mut app), (0, 4));
+        command(&mut app, &mut print, "cursor 0x8c");
+        assert_eq!(app.current_offset(), 0x80);
+        assert_eq!(position(&mut app), (0, 12));
+        command(&mut app, &mut print, "cursor 0x100");
+        assert!(last_line(&print).contains("past the end"));
+        assert_eq!(app.current_offset(), 0x80);
+        assert_eq!(position(&mut app), (0, 12));
+    }
+
+    #[test]
+    fn ascii_print_expands_tabs() {
+        let mut app = memory_app(b"a\tbc\tdefghij\tk\n\tx".to_vec());
+        let mut print = Print::default();
+        app.tabs.current().print_gutter = PrintGutter::Off;
+        let lines: Vec<String> = app.get_ascii_print().iter().map(line_text).collect();
+        assert_eq!(lines, ["a.......bc......defghij.k", "........x"]);
+        command(&mut app, &mut print, "tabwidth 4");
+        let lines: Vec<String> = app.get_ascii_print().iter().map(line_text).collect();
+        assert_eq!(lines, ["a...bc..defghij.k", "....x"]);
+        command(&mut app, &mut print, "tabwidth 0");
+        assert!(last_line(&print).contains("tab width must be between"));
+        assert_eq!(app.tab_width, 4);
+    }
+
+    #[test]
+    fn cloned_tabs_keep_their_offsets() {
+        let data: Vec<u8> = (0..0x400u32).map(|i| (i % 251) as u8).collect();
+        let mut app = memory_app(data.clone());
+        let mut print = Print::default();
+        app.sync_file(&mut print);
+        command(&mut app, &mut print, "offset 0x100");
+        app.sync_file(&mut print);
+        command(&mut app, &mut print, "tab clone");
+        app.sync_file(&mut print);
+        assert_eq!(app.tabs.tabs.len(), 2);
+        assert_eq!(app.current_offset(), 0x100);
+        command(&mut app, &mut print, "offset 0x200");
+        app.sync_file(&mut print);
+        assert_eq!(app.current_bytes()[0], data[0x200]);
+        for (tab, offset) in [("prev", 0x100), ("next", 0x200), ("prev", 0x100)] {
+            command(&mut app, &mut print, &format!("tab {}", tab));
+            app.sync_file(&mut print);
+            assert_eq!(app.current_offset(), offset);
+            assert_eq!(app.current_bytes()[0], data[offset as usize]);
+        }
+    }
+
+    #[test]
+    fn ranges_come_from_args_or_selection() {
+        let data: Vec<u8> = (0u8..64).collect();
+        let mut app = memory_app(data.clone());
+        let mut print = Print::default();
+        insert_at(&mut app, 0, 4);
+        app.toggle_selection();
+        app.tabs.current().cursor_row = 1;
+        app.tabs.current().cursor_column = 2;
+        assert_eq!(app.selection(), Some((4, 19)));
+
+        let file = Scratch::new("carve-selection");
+        let path = file.path();
+        command(&mut app, &mut print, &format!("carve {}", path));
+        assert!(last_line(&print).starts_with("Carved 15 bytes at 0x00000004"));
+        assert_eq!(std::fs::read(&path).unwrap(), &data[4..19]);
+        command(&mut app, &mut print, "xor ff");
+        app.sync_file(&mut print);
+        let xored: Vec<u8> = data[4..19].iter().map(|b| b ^ 0xff).collect();
+        assert_eq!(&app.current_bytes()[4..19], &xored[..]);
+        assert_eq!(app.current_bytes()[19], 19);
+
+        command(&mut app, &mut print, &format!("carve 0x20 0x28 {}", path));
+        assert_eq!(std::fs::read(&path).unwrap(), &data[0x20..0x28]);
+        command(&mut app, &mut print, &format!("carve 8 4 {}", path));
+        assert!(last_line(&print).contains("start offset must be below end offset"));
+        app.toggle_selection();
+        command(&mut app, &mut print, &format!("carve {}", path));
+        assert!(last_line(&print).contains("carve needs <start> <end> or a selection"));
+    }
+
+    #[test]
+    fn asm_reads_past_the_block() {
+        let mut data = vec![0x90u8; 6];
+        data.extend_from_slice(&[0xb8, 0x78, 0x56, 0x34, 0x12]);
+        data.extend_from_slice(&[0x90; 16]);
+        let mut app = memory_app(data);
+        let mut print = Print::default();
+        app.tabs.current().display = Display::Asm;
+        app.tabs.current().block_size = 8;
+        app.sync_file(&mut print);
+        let lines: Vec<String> = app.get_asm().iter().map(line_text).collect();
+        // the mov starting in the block is completed, nothing after it is needed
+        assert_eq!(lines.len(), 7);
+        assert!(lines[6].ends_with("eax,1234`5678h"));
+
+        // a block shorter than the screen is filled from the bytes after it
+        app.tabs.current().block_size = 2;
+        app.sync_file(&mut print);
+        let lines: Vec<String> = app.get_asm().iter().map(line_text).collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines.iter().all(|line| line.ends_with("nop")));
+    }
+
+    #[test]
+    fn encodes_utf16_patterns() {
+        assert_eq!(App::search_bytes("utf16 Ab").unwrap(), [0x41, 0, 0x62, 0]);
+        assert_eq!(
+            App::search_bytes("utf16le  Ab").unwrap(),
+            [0x41, 0, 0x62, 0]
+        );
+        assert_eq!(
+            App::search_bytes("utf16be \"\u{e9}\\0\"").unwrap(),
+            [0, 0xe9, 0, 0]
+        );
+        // outside the basic plane text becomes a surrogate pair
+        assert_eq!(
+            App::search_bytes("utf16 \u{1f600}").unwrap(),
+            [0x3d, 0xd8, 0x00, 0xde]
+        );
+        assert_eq!(App::search_bytes("utf8 Ab").unwrap(), b"utf8 Ab");
+        let err = App::search_bytes("utf16 \"\\xff\"").unwrap_err();
+        assert!(err.to_string().contains("must be valid UTF-8"));
+    }
+
+    #[test]
+    fn encodes_value_patterns() {
+        assert_eq!(App::value_bytes("0x1234 2 le").unwrap(), [0x34, 0x12]);
+        assert_eq!(App::value_bytes("0x1234 2 be").unwrap(), [0x12, 0x34]);
+        assert_eq!(App::value_bytes("255 1 be").unwrap(), [0xff]);
+        assert_eq!(App::value_bytes("1 4 be").unwrap(), [0, 0, 0, 1]);
+        assert_eq!(
+            App::search_bytes("value 0x0102030405060708 8 le").unwrap(),
+            [8, 7, 6, 5, 4, 3, 2, 1]
+        );
+        for (args, message) in [
+            ("256 1 le", "does not fit in 1 bytes"),
+            ("0x10000 2 be", "does not fit in 2 bytes"),
+            ("1 3 le", "invalid width"),
+            ("1 2 middle", "invalid endianness"),
+            ("x 2 le", "invalid value"),
+            ("1 2", "expected value"),
+        ] {
+            let err = App::value_bytes(args).unwrap_err();
+            assert!(err.to_string().contains(message), "{}", args);
+        }
+    }
+
+    #[test]
+    fn search_range_limits_hits() {
+        let mut data = vec![0u8; 256];
+        for at in [0x10, 0x40, 0x7f, 0xc0] {
+            data[at..at + 2].copy_from_slice(b"AB");
+        }
+        let mut app = memory_app(data);
+        let hits = |app: &mut App, query: &str| app.scan_hits(query.to_string()).unwrap().hits;
+        assert_eq!(hits(&mut app, "AB"), [0x10, 0x40, 0x7f, 0xc0]);
+        assert_eq!(hits(&mut app, "AB from 0x41"), [0x7f, 0xc0]);
+        // a hit must lie entirely below the to offset
+        assert_eq!(hits(&mut app, "AB from 0x40 to 0x80"), [0x40]);
+        assert_eq!(hits(&mut app, "AB to 0x81"), [0x10, 0x40, 0x7f]);
+        assert!(hits(&mut app, "AB to 0x10").is_empty());
+
+        assert_eq!(App::search_range("AB").unwrap(), ("AB", 0, u64::MAX));
+        assert_eq!(App::search_range("A B from 1 to 2").unwrap(), ("A B", 1, 2));
+        let err = App::search_range("AB from 0x80 to 0x40").unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("from offset must be below to offset"));
+        let err = App::search_range("AB from zz").unwrap_err();
+        assert!(err.to_string().contains("invalid from offset"));
+    }
+
+    #[test]
+    fn long_patterns_cross_block_boundaries() {
+        let text = |len: usize, base: u8| -> String {
+            (0..len).map(|i| (base + (i % 26) as u8) as char).collect()
+        };
+        let (short, long) = (text(100, b'a'), text(3000, b'A'));
+        // scans read 2048 byte windows, the first hit crosses one and the long one spans two
+        let mut data = vec![0u8; 8000];
+        data[2000..2100].copy_from_slice(short.as_bytes());
+        data[4000..7000].copy_from_slice(long.as_bytes());
+        data[7900..8000].copy_from_slice(short.as_bytes());
+        let mut app = memory_app(data);
+        assert_eq!(app.scan_hits(short).unwrap().hits, [2000, 7900]);
+        assert_eq!(app.scan_hits(long.clone()).unwrap().hits, [4000]);
+        assert!(app
+            .scan_hits(format!("{} to 6999", long))
+            .unwrap()
+            .hits
+            .is_empty());
+    }
+
+    #[test]
+    fn text_insert_overwrites_printable_ascii() {
+        let mut app = memory_app(b"abc\nde".to_vec());
+        app.tabs.current().display = Display::Print;
+        app.tabs.current().print_display = PrintDisplay::ASCIIPrint;
+        insert_at(&mut app, 0, 1);
+        for c in "XY".chars() {
+            app.handle_text_insert(c);
+        }
+        assert_eq!(app.current_bytes(), b"aXY\nde");
+        assert_eq!(app.text_cursor(), Some(3));
+        for c in ['\t', '\u{e9}'] {
+            app.handle_text_insert(c);
+            assert!(app.tabs.current().insert_error);
+            app.tabs.current().insert_error = false;
+        }
+        assert_eq!(app.current_bytes(), b"aXY\nde");
+
+        // the last line has no newline, the cursor stops on the last byte of the file
+        app.tabs.current().cursor_row = 1;
+        app.tabs.current().cursor_column = 5;
+        assert_eq!(app.text_cursor(), Some(5));
+        app.handle_text_insert('Z');
+        assert!(!app.tabs.current().insert_error);
+        assert_eq!(app.current_bytes(), b"aXY\ndZ");
+        app.do_undo();
+        assert_eq!(app.current_bytes(), b"aXY\nde");
+        app.do_undo();
+        assert_eq!(app.current_bytes(), b"aXc\nde");
+        app.do_undo();
+        assert_eq!(app.current_bytes(), b"abc\nde");
+    }
+
+    #[test]
+    fn asm_stops_at_end_of_file() {
+        let (mut app, _print) = asm_app(vec![0x90, 0xff, 0xff]);
+        assert!(app.files.current(0).block.buffer[3..]
+            .iter()
+            .all(|b| *b == 0xff));
+        let lines: Vec<String> = app.get_asm().iter().map(line_text).collect();
+        // the literal 0xff bytes decode, the padding after them does not
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with("nop"));
+        assert!(lines[1].contains("FFFF") && lines[1].ends_with("(bad)"));
+    }
+
+    #[test]
+    fn asm_annotates_rip_relative_targets() {
+        let mut data = vec![0x90u8; 8];
+        data.extend_from_slice(&[0x48, 0x8b, 0x05, 0x10, 0x00, 0x00, 0x00]);
+        let (mut app, mut print) = asm_app(data);
+        assert!(line_text(&app.get_asm()[8]).ends_with("; 0x1f"));
+        assert_eq!(app.cache.asm[8].mem_target, Some(0x1f));
+        // the target is shown as a file offset, whatever the image base
+        app.files.current(0).image_base = 0x400000;
+        app.sync_file(&mut print);
+        assert!(line_text(&app.get_asm()[8]).ends_with("; 0x1f"));
+    }
+
+    #[test]
+    fn changes_diff_against_the_file_on_disk() {
+        let size = 2 * CHANGES_BLOCK as usize;
+        let file = Scratch::with("changes", vec![0u8; size]);
+        let mut app = App::new("test", Vec::new());
+        let mut print = Print::default();
+        app.add_file(&mut print, file.path());
+        app.sync_file(&mut print);
+        let boundary = CHANGES_BLOCK - 2;
+        let fi = app.files.current(0);
+        fi.patch.insert(0x10, vec![1, 0, 1]);
+        fi.patch.insert(0x20, vec![0, 0]);
+        fi.patch.insert(boundary, vec![1, 1, 1, 1]);
+        command(&mut app, &mut print, "changes");
+        assert!(last_line(&print).starts_with("6 bytes differ from"));
+        assert!(last_line(&print).ends_with("in 3 ranges"));
+        let fi = app.files.current(0);
+        let hits = &fi.hhits.hits[fi.hhits.selected];
+        assert_eq!(hits.hits, [0x10, 0x12, boundary]);
+
+        // bytes written to disk behind the editor's back no longer count
+        let mut data = vec![0u8; size];
+        data[boundary as usize..boundary as usize + 4].fill(1);
+        std::fs::write(&file, &data).unwrap();
+        command(&mut app, &mut print, "changes");
+        assert!(last_line(&print).starts_with("2 bytes differ from"));
+    }
+
+    #[test]
+    fn dot_repeats_the_last_command() {
+        let mut app = memory_app(vec![0u8; 16]);
+        let mut print = Print::default();
+        press(&mut app, &mut print, ".");
+        assert_eq!(last_line(&print), "No command to repeat");
+        command(&mut app, &mut print, "add 0 2 01");
+        command(&mut app, &mut print, "  ");
+        press(&mut app, &mut print, ".");
+        app.sync_file(&mut print);
+        assert_eq!(&app.current_bytes()[..3], [2, 2, 0]);
+        press(&mut app, &mut print, ".");
+        app.sync_file(&mut print);
+        assert_eq!(&app.current_bytes()[..3], [3, 3, 0]);
+        // each repeat is its own edit
+        app.do_undo();
+        app.sync_file(&mut print);
+        assert_eq!(&app.current_bytes()[..3], [2, 2, 0]);
+        command(&mut app, &mut print, "bogus");
+        press(&mut app, &mut print, ".");
+        assert!(last_line(&print).contains("bogus"));
+    }
+
+    #[test]
+    fn navigation_wraps_at_file_ends() {
+        // 100 bytes in a 16x4 view, the last full view starts at 0x30
+        let mut app = memory_app(vec![0u8; 100]);
+        let mut print = Print::default();
+        app.on_up(&mut print);
+        assert_eq!(app.current_offset(), 0);
+        command(&mut app, &mut print, "wrap on");
+        app.on_up(&mut print);
+        assert_eq!(app.current_offset(), 0x30);
+        app.on_down(&mut print);
+        assert_eq!(app.current_offset(), 0);
+        app.on_pageup(&mut print);
+        assert_eq!(app.current_offset(), 0x30);
+        app.on_pagedown(&mut print);
+        assert_eq!(app.current_offset(), 0);
+        command(&mut app, &mut print, "wrap yes");
+        assert_eq!(
+            last_line(&print),
+            "unknown value: yes (expected one of: on, off)"
+        );
+        assert!(app.tabs.current().wrap_navigation);
+        command(&mut app, &mut print, "wrap off");
+        app.on_pageup(&mut print);
+        assert_eq!(app.current_offset(), 0);
+        app.files.current(0).block.offset = 0x30;
+        app.on_down(&mut print);
+        app.on_pagedown(&mut print);
+        assert_eq!(app.current_offset(), 0x30);
+    }
+
+    #[test]
+    fn scrolling_down_stops_at_last_row() {
+        assert_eq!(App::max_offset(0, 16, 4), 0);
+        assert_eq!(App::max_offset(10, 16, 4), 0);
+        assert_eq!(App::max_offset(64, 16, 4), 0);
+        assert_eq!(App::max_offset(65, 16, 4), 0x10);
+        assert_eq!(App::max_offset(100, 16, 4), 0x30);
+        let mut app = memory_app(vec![0u8; 100]);
+        let mut print = Print::default();
+        for _ in 0..10 {
+            app.on_down(&mut print);
+        }
+        assert_eq!(app.current_offset(), 0x30);
+        app.files.current(0).block.offset = 0x10;
+        app.on_pagedown(&mut print);
+        assert_eq!(app.current_offset(), 0x30);
+        let mut app = memory_app(vec![0u8; 10]);
+        app.on_pagedown(&mut print);
+        app.on_down(&mut print);
+        assert_eq!(app.current_offset(), 0);
+    }
+
+    #[test]
+    fn unknown_commands_are_reported() {
+        let mut app = memory_app(vec![0u8; 16]);
+        let mut print = Print::default();
+        command(&mut app, &mut print, "ofset 0x10");
+        assert!(last_line(&print).starts_with("unknown command: ofset (expected one of: "));
+        command(&mut app, &mut print, "offset");
+        assert_eq!(
+            last_line(&print),
+            "missing or invalid arguments for command: offset"
+        );
+        command(&mut app, &mut print, "show bogus hex");
+        assert!(last_line(&print).starts_with("unknown show kind: bogus (expected one of: byte"));
+        command(&mut app, &mut print, "show byte bogus");
+        assert!(last_line(&print).starts_with("unknown show mode: bogus"));
+    }
+
+    #[test]
+    fn parses_numbers() {
+        assert_eq!(App::parse_u64_number("42"), Ok(42));
+        assert_eq!(App::parse_u64_number("0x2a"), Ok(42));
+        assert_eq!(App::parse_u64_number("52o"), Ok(42));
+        assert_eq!(App::parse_u64_number("101010b"), Ok(42));
+        assert_eq!(App::parse_u64_number("0xffffffffffffffff"), Ok(u64::MAX));
+        for bad in [
+            "",
+            "zzz",
+            "0x",
+            "0xg1",
+            "12o3",
+            "2b",
+            "-1",
+            "18446744073709551616",
+        ] {
+            assert!(App::parse_u64_number(bad).is_err(), "{}", bad);
+        }
+        assert!(App::parse_u64_number("0x10000000000000000").is_err());
+    }
+
+    #[test]
+    fn bad_numeric_arguments_are_reported() {
+        let mut app = memory_app(vec![0u8; 16]);
+        let mut print = Print::default();
+        let block_size = app.tabs.current().block_size;
+        command(&mut app, &mut print, "offset zzz");
+        assert_eq!(
+            last_line(&print),
+            "invalid offset: zzz: invalid digit found in string"
+        );
+        command(&mut app, &mut print, "block_size 0x10000000000000000");
+        assert_eq!(
+            last_line(&print),
+            "invalid block size: 0x10000000000000000: number too large to fit in target type"
+        );
+        command(&mut app, &mut print, "block_size 0");
+        assert_eq!(
+            last_line(&print),
+            format!("block size must be between 1 and {}", MAX_BLOCK_SIZE)
+        );
+        assert_eq!(app.current_offset(), 0);
+        assert_eq!(app.tabs.current().block_size, block_size);
+    }
+
+    // types keys at the start of the element under the cursor, returns the byte or the error
+    fn type_element(app: &mut App, mode: ElementMode, keys: &str) -> Result<u8, usize> {
+        app.tabs.current().element_mode = mode;
+        insert_at(app, 0, 0);
+        for c in keys.chars() {
+            app.handle_insert(c);
+            if app.tabs.current().insert_error {
+                return Err(app.tabs.current().insert_index);
+            }
+        }
+        Ok(app.files.current(0).block.buffer[0])
+    }
+
+    #[test]
+    fn insert_checks_digits_and_range() {
+        let mut app = memory_app(vec![0u8; 16]);
+        assert_eq!(type_element(&mut app, ElementMode::Dec, "255"), Ok(255));
+        assert_eq!(type_element(&mut app, ElementMode::Dec, "3"), Err(0));
+        assert_eq!(type_element(&mut app, ElementMode::Dec, "26"), Err(1));
+        assert_eq!(type_element(&mut app, ElementMode::Dec, "a"), Err(0));
+        assert_eq!(type_element(&mut app, ElementMode::Oct, "377"), Ok(255));
+        assert_eq!(type_element(&mut app, ElementMode::Oct, "4"), Err(0));
+        assert_eq!(type_element(&mut app, ElementMode::Oct, "18"), Err(1));
+        assert_eq!(
+            type_element(&mut app, ElementMode::Bin, "10100101"),
+            Ok(0xa5)
+        );
+        assert_eq!(type_element(&mut app, ElementMode::Bin, "2"), Err(0));
+        // a rejected digit leaves the byte and the seeded digits as they were
+        assert_eq!(type_element(&mut app, ElementMode::Hex, "g"), Err(0));
+        assert_eq!(app.files.current(0).block.buffer[0], 0xa5);
+        assert_eq!(&app.tabs.current().insert_vector[..2], b"a5");
+    }
+
+    #[test]
+    fn oword_takes_full_width_input() {
+        let mut app = memory_app(vec![0u8; 32]);
+        app.tabs.current().element_display = ElementDisplay::OWord;
+        let bits = format!("1{}1", "0".repeat(126));
+        assert_eq!(INSERT_VECTOR_SIZE, bits.len());
+        assert_eq!(type_element(&mut app, ElementMode::Bin, &bits), Ok(0x01));
+        let mut expected = [0u8; 16];
+        expected[0] = 0x01;
+        expected[15] = 0x80;
+        assert_eq!(&app.files.current(0).block.buffer[..16], &expected);
+        assert_eq!(app.tabs.current().insert_index, 0);
+
+        // digits are checked against the seeded value, so clear it first
+        let zeros = "0".repeat(39);
+        assert_eq!(type_element(&mut app, ElementMode::Dec, &zeros), Ok(0));
+        let max = u128::MAX.to_string();
+        assert_eq!(type_element(&mut app, ElementMode::Dec, &max), Ok(0xff));
+        assert_eq!(&app.files.current(0).block.buffer[..16], &[0xff; 16]);
+        assert_eq!(app.files.current(0).block.buffer[16], 0);
+    }
+
+    #[test]
+    fn dot_keeps_existing_digits() {
+        let mut app = memory_app(vec![0xab, 0xcd, 0x34, 0x12]);
+        assert_eq!(type_element(&mut app, ElementMode::Hex, "."), Ok(0xab));
+        assert!(app.files.current(0).patch.is_empty());
+        assert_eq!(type_element(&mut app, ElementMode::Hex, ".7"), Ok(0xa7));
+        app.tabs.current().element_display = ElementDisplay::Word;
+        insert_at(&mut app, 0, 2);
+        assert_eq!(&app.tabs.current().insert_vector[..4], b"1234");
+        for c in "..5".chars() {
+            app.handle_insert(c);
+        }
+        assert_eq!(app.files.current(0).patch.get(&2), Some(&vec![0x54, 0x12]));
+    }
+
+    #[test]
+    fn bit_edits_flip_and_undo() {
+        let mut app = memory_app(vec![0u8; 16]);
+        let mut print = Print::default();
+        command(&mut app, &mut print, "show byte bit");
+        assert_eq!(app.tabs.current().element_mode, ElementMode::Bit);
+        insert_at(&mut app, 0, 1);
+        app.handle_insert('0');
+        app.handle_insert('7');
+        assert_eq!(app.files.current(0).patch.get(&1), Some(&vec![0x81]));
+        assert!(line_text(&app.get_binbyte()[1]).contains("00000000 10000001 00000000"));
+        app.handle_insert('8');
+        assert!(app.tabs.current().insert_error);
+        app.do_undo();
+        assert_eq!(app.files.current(0).block.buffer[1], 0x01);
+        app.do_undo();
+        assert!(app.files.current(0).patch.is_empty());
+        assert!(!line_text(&app.get_binbyte()[1]).contains('1'));
+    }
+
+    #[test]
+    fn xor_range_and_undo() {
+        let data: Vec<u8> = (0u8..16).collect();
+        let mut app = memory_app(data.clone());
+        let mut print = Print::default();
+        command(&mut app, &mut print, "xor 2 7 f00f");
+        app.sync_file(&mut print);
+        let mut expected = data.clone();
+        for (i, b) in expected[2..7].iter_mut().enumerate() {
+            *b ^= [0xf0, 0x0f][i % 2];
+        }
+        assert_eq!(app.current_bytes(), &expected[..]);
+        command(&mut app, &mut print, "xor 7 2 ff");
+        assert_eq!(last_line(&print), "start offset must be below end offset");
+        command(&mut app, &mut print, "xor 2 4 zz");
+        assert_eq!(last_line(&print), "invalid key: zz: expected hex bytes");
+        app.do_undo();
+        assert_eq!(app.current_bytes(), &data[..]);
+        app.do_redo();
+        assert_eq!(app.current_bytes(), &expected[..]);
+    }
+
+    #[test]
+    fn xor_twice_restores_the_data() {
+        let data: Vec<u8> = (0u8..64).collect();
+        let mut app = memory_app(data.clone());
+        let mut print = Print::default();
+        command(&mut app, &mut print, "xor 3 0x3d deadbeef");
+        app.sync_file(&mut print);
+        assert_ne!(app.current_bytes(), &data[..]);
+        command(&mut app, &mut print, "xor 3 0x3d deadbeef");
+        app.sync_file(&mut print);
+        assert_eq!(app.current_bytes(), &data[..]);
+        command(&mut app, &mut print, "rol 0 4 9");
+        assert_eq!(last_line(&print), "bit count must be between 0 and 7");
+        command(&mut app, &mut print, "rol 0 1 7");
+        command(&mut app, &mut print, "ror 0 1 7");
+        app.sync_file(&mut print);
+        assert_eq!(app.current_bytes(), &data[..]);
+    }
+
+    #[test]
+    fn range_edits_across_blocks_are_written() {
+        let file = Scratch::with("xor-write", vec![0u8; 0x1000]);
+        let mut app = App::new("test", Vec::new());
+        let mut print = Print::default();
+        app.add_file(&mut print, file.path());
+        app.sync_file(&mut print);
+        command(&mut app, &mut print, "xor 0x7f0 0x810 ff");
+        assert!(app
+            .files
+            .current(0)
+            .patch
+            .keys()
+            .all(|k| *k == 0x7f0 || *k == 0x800));
+        app.on_write(&mut print);
+        assert!(last_line(&print).starts_with("Wrote 32 bytes"));
+        let mut expected = vec![0u8; 0x1000];
+        expected[0x7f0..0x810].fill(0xff);
+        assert_eq!(std::fs::read(&file).unwrap(), expected);
+        assert!(app.files.current(0).patch.is_empty());
+    }
+
+    #[test]
+    fn no_op_transforms_leave_the_file_clean() {
+        let mut app = memory_app((0u8..64).collect());
+        let mut print = Print::default();
+        command(&mut app, &mut print, "xor 0 0x30 00");
+        assert_eq!(last_line(&print), "xor 48 bytes at 0x00000000");
+        assert!(app.files.current(0).patch.is_empty());
+        command(&mut app, &mut print, "add 4 8 01");
+        app.sync_file(&mut print);
+        command(&mut app, &mut print, "sub 4 8 01");
+        assert!(app.files.current(0).patch.is_empty());
+    }
+
+    #[test]
+    fn nop_overwrites_the_instruction_at_the_cursor() {
+        // mov eax,12345678h then ret
+        let data = vec![0x90, 0xb8, 0x78, 0x56, 0x34, 0x12, 0xc3];
+        let (mut app, mut print) = asm_app(data.clone());
+        app.get_asm();
+        insert_at(&mut app, 1, 0);
+        command(&mut app, &mut print, "nop");
+        assert_eq!(last_line(&print), "nop 5 bytes at 0x00000001");
+        app.sync_file(&mut print);
+        assert!(app.current_bytes()[..6].iter().all(|b| *b == 0x90));
+        let lines: Vec<String> = app.get_asm().iter().map(line_text).collect();
+        assert!(lines[..6].iter().all(|line| line.ends_with("nop")));
+        assert!(lines[6].ends_with("ret"));
+        app.do_undo();
+        app.sync_file(&mut print);
+        assert_eq!(app.current_bytes(), &data[..]);
+        assert!(line_text(&app.get_asm()[1]).ends_with("eax,1234`5678h"));
+    }
+
+    #[test]
+    fn loaded_sessions_split_oversized_patches() {
+        let file = Scratch::with("session-data", vec![0u8; 0x2000]);
+        let session = Scratch::new("session");
+        let mut app = App::new("test", Vec::new());
+        let mut print = Print::default();
+        app.add_file(&mut print, file.path());
+        let fi = app.files.current(0);
+        fi.patch.insert(0x10, Vec::new());
+        fi.patch.insert(0x100, vec![1; 0x1000]);
+        command(
+            &mut app,
+            &mut print,
+            &format!("session save {}", session.path()),
+        );
+        command(
+            &mut app,
+            &mut print,
+            &format!("session load {}", session.path()),
+        );
+        assert!(last_line(&print).starts_with("Session loaded"));
+        let sizes: Vec<(u64, usize)> = app
+            .files
+            .current(0)
+            .patch
+            .iter()
+            .map(|(at, bytes)| (*at, bytes.len()))
+            .collect();
+        assert_eq!(sizes, [(0x100, 0x700), (0x800, 0x800), (0x1000, 0x100)]);
+    }
+
+    #[test]
+    fn bookmarked_rows_are_marked_in_the_gutter() {
+        let mut app = memory_app(vec![0u8; 256]);
+        let mut print = Print::default();
+        app.sync_file(&mut print);
+        command(&mut app, &mut print, "offset 0x24");
+        command(&mut app, &mut print, "bookmark add data");
+        assert_eq!(last_line(&print), "Bookmark data at 0x24");
+        command(&mut app, &mut print, "offset 0x10");
+        app.sync_file(&mut print);
+        let bookmark = app.theme.bookmark;
+        let lines = app.on_draw().clone();
+        // the header comes first, then the rows at 0x10 and 0x20
+        assert_eq!(lines[1].0[0].content, "0x00000010 ");
+        assert_ne!(lines[1].0[0].style, bookmark);
+        assert_eq!(lines[2].0[0].content, "0x00000020>");
+        assert_eq!(lines[2].0[0].style, bookmark);
+
+        command(&mut app, &mut print, "bookmark go data");
+        assert_eq!(app.current_offset(), 0x24);
+        command(&mut app, &mut print, "bookmark del data");
+        command(&mut app, &mut print, "bookmark go data");
+        assert_eq!(last_line(&print), "No bookmark named data");
+        command(&mut app, &mut print, "bookmark");
+        assert_eq!(last_line(&print), "No bookmarks");
+        command(&mut app, &mut print, "bookmark jump data");
+        assert!(last_line(&print).starts_with("unknown bookmark command: jump"));
+    }
+
+    #[test]
+    fn bswap_swaps_whole_groups() {
+        let mut app = memory_app((0u8..16).collect());
+        let mut print = Print::default();
+        command(&mut app, &mut print, "bswap 0 8 4");
+        assert_eq!(last_line(&print), "bswap 8 bytes at 0x00000000");
+        command(&mut app, &mut print, "bswap 8 0xb 2");
+        assert_eq!(last_line(&print), "range length is not a multiple of 2");
+        command(&mut app, &mut print, "bswap 8 0xc 3");
+        assert_eq!(last_line(&print), "invalid width: 3: expected 2, 4 or 8");
+        // the range is cut at the end of file
+        command(&mut app, &mut print, "bswap 0xc 0x14 4");
+        assert_eq!(last_line(&print), "bswap 4 bytes at 0x0000000c");
+        app.sync_file(&mut print);
+        assert_eq!(
+            app.current_bytes(),
+            [3, 2, 1, 0, 7, 6, 5, 4, 8, 9, 10, 11, 15, 14, 13, 12]
+        );
+        app.do_undo();
+        app.do_undo();
+        assert_eq!(app.current_bytes(), (0u8..16).collect::<Vec<u8>>());
+        command(&mut app, &mut print, "bswap 0 0x10 8");
+        command(&mut app, &mut print, "bswap 0 4 2");
+        app.sync_file(&mut print);
+        assert_eq!(
+            app.current_bytes(),
+            [6, 7, 4, 5, 3, 2, 1, 0, 15, 14, 13, 12, 11, 10, 9, 8]
+        );
+    }
+}
@@ -1,10 +1,19 @@
-#[derive(Clone, Eq, PartialEq)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Block {
+    #[serde(skip)]
     pub buffer: Vec<u8>,
+    #[serde(skip)]
     pub source: Vec<u8>,
     pub offset: u64,
     pub size: u64,
+    // bytes actually read, the rest of the buffer is padding past the end of file
+    #[serde(skip)]
+    pub data_len: usize,
+    #[serde(skip)]
     pub prev_offset: u64,
+    #[serde(skip)]
     pub prev_size: u64,
 }
 
@@ -15,8 +24,13 @@ impl Block {
             source: Vec::with_capacity(size),
             offset: 0,
             size: size as u64,
+            data_len: 0,
             prev_offset: 0,
             prev_size: 0,
         }
     }
+
+    pub fn data(&self) -> &[u8] {
+        &self.buffer[..std::cmp::min(self.data_len, self.buffer.len())]
+    }
 }
@@ -0,0 +1,37 @@
+#[cfg(feature = "clipboard")]
+pub struct Clipboard {
+    // kept alive, on X11 the copied text is only served while the owner exists
+    inner: Option<arboard::Clipboard>,
+}
+
+#[cfg(feature = "clipboard")]
+impl Clipboard {
+    pub fn default() -> Clipboard {
+        Clipboard { inner: None }
+    }
+
+    pub fn copy(&mut self, text: String) -> Result<(), String> {
+        if self.inner.is_none() {
+            self.inner = Some(arboard::Clipboard::new().map_err(|e| e.to_string())?);
+        }
+        self.inner
+            .as_mut()
+            .unwrap()
+            .set_text(text)
+            .map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(not(feature = "clipboard"))]
+pub struct Clipboard {}
+
+#[cfg(not(feature = "clipboard"))]
+impl Clipboard {
+    pub fn default() -> Clipboard {
+        Clipboard {}
+    }
+
+    pub fn copy(&mut self, _text: String) -> Result<(), String> {
+        Err("built without clipboard feature".to_string())
+    }
+}
@@ -0,0 +1,33 @@
+#[cfg(not(test))]
+use std::env;
+use std::path::PathBuf;
+
+#[cfg(not(test))]
+pub fn config_path(name: &str) -> Option<PathBuf> {
+    let mut path;
+    if let Some(dir) = env::var_os("XDG_CONFIG_HOME") {
+        path = PathBuf::from(dir);
+    } else {
+        path = PathBuf::from(env::var_os("HOME")?);
+        path.push(".config");
+    }
+    path.push("rsreit");
+    path.push(name);
+    Some(path)
+}
+
+#[cfg(test)]
+thread_local! {
+    // every test runs on its own thread, so tests never share a recent list
+    static CONFIG: crate::scratch::Scratch = {
+        let id = format!("{:?}", std::thread::current().id());
+        let id: String = id.chars().filter(|c| c.is_ascii_digit()).collect();
+        crate::scratch::Scratch::new(&format!("config-{}", id))
+    };
+}
+
+// tests open files too, which must not end up in the user's recent list
+#[cfg(test)]
+pub fn config_path(name: &str) -> Option<PathBuf> {
+    Some(CONFIG.with(|dir| dir.join(name)))
+}
@@ -1,13 +1,15 @@
 use crate::print::Print;
 use crate::{app::App, ui};
 use crossterm::{
+    cursor::Show,
     event::{self, DisableMouseCapture, EnableMouseCapture, Event},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use std::{
     error::Error,
-    io,
+    io::{self, Write},
+    panic,
     time::{Duration, Instant},
 };
 use tui::{
@@ -15,23 +17,40 @@ use tui::{
     Terminal,
 };
 
-pub fn run(tick_rate: Duration, paths: Vec<String>) -> Result<(), Box<dyn Error>> {
+fn restore_terminal<W: Write>(out: &mut W, mouse: bool) -> io::Result<()> {
+    disable_raw_mode()?;
+    if mouse {
+        execute!(out, DisableMouseCapture)?;
+    }
+    execute!(out, LeaveAlternateScreen, Show)
+}
+
+pub fn run<'a>(
+    tick_rate: Duration,
+    mouse: bool,
+    app: App<'a>,
+    print: Print<'a>,
+) -> Result<(), Box<dyn Error>> {
+    // put the terminal back before the panic message is printed
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let _ = restore_terminal(&mut io::stdout(), mouse);
+        default_hook(info);
+    }));
+
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    execute!(stdout, EnterAlternateScreen)?;
+    if mouse {
+        execute!(stdout, EnableMouseCapture)?;
+    }
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let app = App::new("rsreit", paths.to_vec());
-    let print = Print::default();
     let res = run_app(&mut terminal, app, print, tick_rate);
+    let _ = panic::take_hook();
 
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
+    restore_terminal(terminal.backend_mut(), mouse)?;
     terminal.show_cursor()?;
 
     if let Err(err) = res {
@@ -49,14 +68,15 @@ fn run_app<'a, B: Backend>(
 ) -> io::Result<()> {
     let mut last_tick = Instant::now();
 
-    for path in &app.paths {
-        app.files.add(path.to_string(), &mut app.tabs);
+    for path in std::mem::take(&mut app.paths) {
+        app.add_file(&mut print, path);
     }
 
     app.sync_file(&mut print);
     loop {
         terminal.draw(|f| ui::draw(f, &mut app, &mut print))?;
 
+        // block until input arrives or the next tick is due, so an idle editor sleeps
         let timeout = tick_rate
             .checked_sub(last_tick.elapsed())
             .unwrap_or_else(|| Duration::from_secs(0));
@@ -67,7 +87,7 @@ fn run_app<'a, B: Backend>(
         }
         app.sync_file(&mut print);
         if last_tick.elapsed() >= tick_rate {
-            app.on_tick();
+            app.on_tick(&mut print);
             last_tick = Instant::now();
         }
         if app.should_quit {
@@ -75,3 +95,20 @@ fn run_app<'a, B: Backend>(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn restore_leaves_alternate_screen() {
+        let mut out = Vec::new();
+        restore_terminal(&mut out, false).unwrap();
+        assert_eq!(out, b"\x1b[?1049l\x1b[?25h");
+        let mut out = Vec::new();
+        restore_terminal(&mut out, true).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("\x1b[?1000l"));
+        assert!(out.ends_with("\x1b[?1049l\x1b[?25h"));
+    }
+}
@@ -0,0 +1,71 @@
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64_value(c: u8) -> Option<u32> {
+    match c {
+        b'-' => Some(62),
+        b'_' => Some(63),
+        _ => BASE64_ALPHABET
+            .iter()
+            .position(|v| *v == c)
+            .map(|v| v as u32),
+    }
+}
+
+// standard and url-safe alphabets, whitespace is skipped and padding is optional
+pub fn base64(input: &[u8]) -> Result<Vec<u8>, String> {
+    let mut output = Vec::with_capacity(input.len() * 3 / 4);
+    let mut acc = 0u32;
+    let mut bits = 0;
+    let mut padding = 0;
+    for (i, c) in input.iter().enumerate() {
+        if c.is_ascii_whitespace() {
+            continue;
+        }
+        if *c == b'=' {
+            padding += 1;
+            continue;
+        }
+        if padding > 0 {
+            return Err(format!("data after padding at +0x{:x}", i));
+        }
+        let value = match base64_value(*c) {
+            Some(value) => value,
+            None => return Err(format!("invalid base64 byte 0x{:02x} at +0x{:x}", c, i)),
+        };
+        acc = (acc << 6) | value;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            output.push((acc >> bits) as u8);
+            acc &= (1 << bits) - 1;
+        }
+    }
+    if bits >= 6 || padding > 2 {
+        return Err("truncated base64 input".to_string());
+    }
+    Ok(output)
+}
+
+// pairs of hex digits, whitespace is skipped
+pub fn hex(input: &[u8]) -> Result<Vec<u8>, String> {
+    let mut output = Vec::with_capacity(input.len() / 2);
+    let mut high: Option<u8> = None;
+    for (i, c) in input.iter().enumerate() {
+        if c.is_ascii_whitespace() {
+            continue;
+        }
+        let digit = match (*c as char).to_digit(16) {
+            Some(digit) => digit as u8,
+            None => return Err(format!("invalid hex byte 0x{:02x} at +0x{:x}", c, i)),
+        };
+        match high.take() {
+            Some(h) => output.push(h << 4 | digit),
+            None => high = Some(digit),
+        }
+    }
+    if high.is_some() {
+        return Err("odd number of hex digits".to_string());
+    }
+    Ok(output)
+}
@@ -0,0 +1,200 @@
+use crate::modes::AsmArch;
+use iced_x86::{
+    Decoder, DecoderOptions, FlowControl, Formatter, FormatterOutput, FormatterTextKind,
+    Instruction, OpKind,
+};
+
+pub struct DecodedInstruction {
+    pub ip: u64,
+    pub len: usize,
+    pub target: Option<u64>,
+    // address of a RIP/EIP relative memory operand
+    pub mem_target: Option<u64>,
+    pub invalid: bool,
+    pub text: Vec<(String, FormatterTextKind)>,
+}
+
+// decode and format step of the asm view, one implementation per backend
+pub trait Disassembler {
+    fn disassemble(&mut self, bytes: &[u8], ip: u64) -> Vec<DecodedInstruction>;
+}
+
+struct AsmFormatterOutput {
+    pub vec: Vec<(String, FormatterTextKind)>,
+}
+
+impl AsmFormatterOutput {
+    pub fn new() -> Self {
+        Self { vec: Vec::new() }
+    }
+}
+
+impl FormatterOutput for AsmFormatterOutput {
+    fn write(&mut self, text: &str, kind: FormatterTextKind) {
+        self.vec.push((String::from(text), kind));
+    }
+}
+
+pub struct X86<'f, F: Formatter> {
+    pub bitness: u32,
+    pub formatter: &'f mut F,
+}
+
+impl<'f, F: Formatter> X86<'f, F> {
+    fn branch_target(instruction: &Instruction) -> Option<u64> {
+        match instruction.flow_control() {
+            FlowControl::UnconditionalBranch
+            | FlowControl::ConditionalBranch
+            | FlowControl::Call => match instruction.op0_kind() {
+                OpKind::NearBranch16 | OpKind::NearBranch32 | OpKind::NearBranch64 => {
+                    Some(instruction.near_branch_target())
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+impl<'f, F: Formatter> Disassembler for X86<'f, F> {
+    fn disassemble(&mut self, bytes: &[u8], ip: u64) -> Vec<DecodedInstruction> {
+        let mut lines = Vec::new();
+        let mut decoder = Decoder::with_ip(self.bitness, bytes, ip, DecoderOptions::NONE);
+        // decode_out() writes every field, so one instruction is reused for the whole block
+        let mut instruction = Instruction::default();
+        while decoder.can_decode() {
+            decoder.decode_out(&mut instruction);
+            let mut output = AsmFormatterOutput::new();
+            self.formatter.format(&instruction, &mut output);
+            lines.push(DecodedInstruction {
+                ip: instruction.ip(),
+                len: instruction.len(),
+                target: Self::branch_target(&instruction),
+                mem_target: if instruction.is_ip_rel_memory_operand() {
+                    Some(instruction.ip_rel_memory_address())
+                } else {
+                    None
+                },
+                invalid: instruction.is_invalid(),
+                text: output.vec,
+            });
+        }
+        lines
+    }
+}
+
+#[cfg(feature = "capstone")]
+pub struct CapstoneDisassembler {
+    cs: capstone::Capstone,
+    // bytes skipped over an undecodable instruction
+    skip: usize,
+}
+
+#[cfg(feature = "capstone")]
+impl CapstoneDisassembler {
+    fn new(arch: AsmArch) -> Result<CapstoneDisassembler, String> {
+        use capstone::prelude::*;
+        let (cs, skip) = match arch {
+            AsmArch::Arm => (
+                Capstone::new().arm().mode(arch::arm::ArchMode::Arm).build(),
+                4,
+            ),
+            AsmArch::Arm64 => (
+                Capstone::new()
+                    .arm64()
+                    .mode(arch::arm64::ArchMode::Arm)
+                    .build(),
+                4,
+            ),
+            _ => return Err(format!("{:?} is decoded by iced-x86", arch)),
+        };
+        Ok(CapstoneDisassembler {
+            cs: cs.map_err(|e| e.to_string())?,
+            skip,
+        })
+    }
+}
+
+#[cfg(feature = "capstone")]
+impl Disassembler for CapstoneDisassembler {
+    fn disassemble(&mut self, bytes: &[u8], ip: u64) -> Vec<DecodedInstruction> {
+        let mut lines = Vec::new();
+        let mut pos = 0;
+        while pos < bytes.len() {
+            let address = ip + pos as u64;
+            let decoded = self.cs.disasm_count(&bytes[pos..], address, 1);
+            let line = match decoded.as_ref().ok().and_then(|insns| insns.iter().next()) {
+                Some(insn) => DecodedInstruction {
+                    ip: address,
+                    len: insn.bytes().len(),
+                    target: None,
+                    mem_target: None,
+                    invalid: false,
+                    text: vec![
+                        (
+                            format!("{:<8}", insn.mnemonic().unwrap_or("")),
+                            FormatterTextKind::Mnemonic,
+                        ),
+                        (
+                            insn.op_str().unwrap_or("").to_string(),
+                            FormatterTextKind::Text,
+                        ),
+                    ],
+                },
+                None => DecodedInstruction {
+                    ip: address,
+                    len: std::cmp::min(self.skip, bytes.len() - pos),
+                    target: None,
+                    mem_target: None,
+                    invalid: true,
+                    text: vec![("(bad)".to_string(), FormatterTextKind::Text)],
+                },
+            };
+            pos += line.len;
+            lines.push(line);
+        }
+        lines
+    }
+}
+
+#[cfg(feature = "capstone")]
+pub fn other(arch: AsmArch) -> Result<Box<dyn Disassembler>, String> {
+    Ok(Box::new(CapstoneDisassembler::new(arch)?))
+}
+
+#[cfg(not(feature = "capstone"))]
+pub fn other(arch: AsmArch) -> Result<Box<dyn Disassembler>, String> {
+    Err(format!(
+        "{:?} needs a build with the capstone feature",
+        arch
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use iced_x86::NasmFormatter;
+
+    fn decode(bitness: u32, bytes: &[u8], ip: u64) -> Vec<DecodedInstruction> {
+        let mut formatter = NasmFormatter::new();
+        let mut x86 = X86 {
+            bitness,
+            formatter: &mut formatter,
+        };
+        x86.disassemble(bytes, ip)
+    }
+
+    #[test]
+    fn resolves_rip_relative_operands() {
+        // mov rax,[rip+0x10] at 8, the next instruction starts at 0xf
+        let lines = decode(64, &[0x48, 0x8b, 0x05, 0x10, 0x00, 0x00, 0x00, 0x90], 8);
+        assert_eq!(lines[0].mem_target, Some(0x1f));
+        assert_eq!(lines[1].mem_target, None);
+        // negative displacement reaches back before the instruction
+        let lines = decode(64, &[0x8b, 0x05, 0xf0, 0xff, 0xff, 0xff], 0x100);
+        assert_eq!(lines[0].mem_target, Some(0xf6));
+        // the same encoding is an absolute address in 32-bit code
+        let lines = decode(32, &[0x8b, 0x05, 0x10, 0x00, 0x00, 0x00], 8);
+        assert_eq!(lines[0].mem_target, None);
+    }
+}
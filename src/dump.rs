@@ -0,0 +1,271 @@
+use crate::app::App;
+use crate::disasm::{self, Disassembler, X86};
+use crate::modes::{
+    element_display_size, element_input_width, AsmArch, ElementDisplay, ElementMode,
+};
+use clap::ArgMatches;
+use iced_x86::NasmFormatter;
+use serde::Serialize;
+use std::error::Error;
+use std::fs;
+use std::io::{Read, Seek, SeekFrom};
+
+const DEFAULT_LENGTH: u64 = 256;
+const ROW_SIZE: usize = 16;
+const DUMP_KINDS: &[&str] = &["byte", "word", "dword", "qword", "oword", "asm"];
+const DUMP_MODES: &[&str] = &["hex", "dec", "oct", "bin"];
+const DUMP_ARCHS: &[&str] = &["x86", "x86_64", "arm", "arm64"];
+
+// --json schema for element kinds, one object per element:
+// offset of its first byte, its bytes as hex in file order
+// and its little endian value in the chosen mode, zero padded like insert mode
+#[derive(Serialize)]
+struct ElementEntry {
+    offset: u64,
+    bytes: String,
+    decoded: String,
+}
+
+// --json schema for asm, one object per instruction:
+// file offset it was decoded at (no image base is applied),
+// its bytes as hex and the formatted instruction
+#[derive(Serialize)]
+struct AsmEntry {
+    offset: u64,
+    bytes: String,
+    text: String,
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn keyword<'k>(what: &str, value: &'k str, valid: &[&str]) -> Result<&'k str, Box<dyn Error>> {
+    if valid.contains(&value) {
+        Ok(value)
+    } else {
+        Err(format!("unknown {} {}, valid: {}", what, value, valid.join(" ")).into())
+    }
+}
+
+fn number(matches: &ArgMatches, name: &str, default: u64) -> Result<u64, Box<dyn Error>> {
+    match matches.get_one::<String>(name) {
+        Some(input) => App::parse_u64_number(input)
+            .map_err(|e| format!("invalid {} {}: {}", name, input, e).into()),
+        None => Ok(default),
+    }
+}
+
+fn read(path: &str, offset: u64, length: u64) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut file = fs::File::open(path).map_err(|e| format!("cannot open {}: {}", path, e))?;
+    file.seek(SeekFrom::Start(offset))?;
+    let mut data = Vec::new();
+    file.take(length).read_to_end(&mut data)?;
+    Ok(data)
+}
+
+fn element_display(kind: &str) -> ElementDisplay {
+    match kind {
+        "word" => ElementDisplay::Word,
+        "dword" => ElementDisplay::DWord,
+        "qword" => ElementDisplay::QWord,
+        "oword" => ElementDisplay::OWord,
+        _ => ElementDisplay::Byte,
+    }
+}
+
+fn element_mode(mode: &str) -> ElementMode {
+    match mode {
+        "dec" => ElementMode::Dec,
+        "oct" => ElementMode::Oct,
+        "bin" => ElementMode::Bin,
+        _ => ElementMode::Hex,
+    }
+}
+
+fn decode_element(bytes: &[u8], display: ElementDisplay, mode: ElementMode) -> String {
+    let mut le = [0u8; 16];
+    le[..bytes.len()].copy_from_slice(bytes);
+    let value = u128::from_le_bytes(le);
+    let width = element_input_width(display, mode) as usize;
+    match mode {
+        ElementMode::Hex => format!("{:0width$x}", value, width = width),
+        ElementMode::Dec => format!("{:0width$}", value, width = width),
+        ElementMode::Oct => format!("{:0width$o}", value, width = width),
+        ElementMode::Bin | ElementMode::Bit => format!("{:0width$b}", value, width = width),
+    }
+}
+
+// a trailing partial element is left out
+fn dump_elements(
+    data: &[u8],
+    offset: u64,
+    kind: &str,
+    mode: &str,
+    json: bool,
+) -> Result<String, Box<dyn Error>> {
+    let display = element_display(kind);
+    let mode = element_mode(mode);
+    let size = element_display_size(display) as usize;
+    let entries: Vec<ElementEntry> = data
+        .chunks_exact(size)
+        .enumerate()
+        .map(|(i, bytes)| ElementEntry {
+            offset: offset + (i * size) as u64,
+            bytes: hex(bytes),
+            decoded: decode_element(bytes, display, mode),
+        })
+        .collect();
+    if json {
+        return Ok(serde_json::to_string_pretty(&entries)? + "\n");
+    }
+    let mut out = String::new();
+    for row in entries.chunks(std::cmp::max(ROW_SIZE / size, 1)) {
+        let values: Vec<&str> = row.iter().map(|e| e.decoded.as_str()).collect();
+        out.push_str(&format!("0x{:08x}  {}\n", row[0].offset, values.join(" ")));
+    }
+    Ok(out)
+}
+
+fn dump_asm(data: &[u8], offset: u64, arch: &str, json: bool) -> Result<String, Box<dyn Error>> {
+    let arch = match arch {
+        "x86" => AsmArch::X86,
+        "arm" => AsmArch::Arm,
+        "arm64" => AsmArch::Arm64,
+        _ => AsmArch::X86_64,
+    };
+    let mut formatter = NasmFormatter::new();
+    let mut x86;
+    let mut other;
+    let disassembler: &mut dyn Disassembler = match arch {
+        AsmArch::X86 | AsmArch::X86_64 => {
+            x86 = X86 {
+                bitness: if arch == AsmArch::X86 { 32 } else { 64 },
+                formatter: &mut formatter,
+            };
+            &mut x86
+        }
+        _ => {
+            other = disasm::other(arch)?;
+            other.as_mut()
+        }
+    };
+    let entries: Vec<AsmEntry> = disassembler
+        .disassemble(data, offset)
+        .into_iter()
+        .map(|instruction| {
+            let start = (instruction.ip - offset) as usize;
+            AsmEntry {
+                offset: instruction.ip,
+                bytes: hex(&data[start..start + instruction.len]),
+                text: instruction.text.into_iter().map(|(text, _)| text).collect(),
+            }
+        })
+        .collect();
+    if json {
+        return Ok(serde_json::to_string_pretty(&entries)? + "\n");
+    }
+    let mut out = String::new();
+    for entry in entries {
+        out.push_str(&format!(
+            "0x{:08x}  {:<32} {}\n",
+            entry.offset, entry.bytes, entry.text
+        ));
+    }
+    Ok(out)
+}
+
+// prints a range of a file as the element or asm view would show it, without the ui
+pub fn run(matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    print!("{}", dump(matches)?);
+    Ok(())
+}
+
+fn dump(matches: &ArgMatches) -> Result<String, Box<dyn Error>> {
+    let path = matches
+        .get_one::<String>("NAME")
+        .ok_or("dump needs a file")?;
+    let offset = number(matches, "offset", 0)?;
+    let length = number(matches, "length", DEFAULT_LENGTH)?;
+    let kind = keyword(
+        "kind",
+        matches.get_one::<String>("kind").map_or("byte", |s| s),
+        DUMP_KINDS,
+    )?;
+    let mode = keyword(
+        "mode",
+        matches.get_one::<String>("mode").map_or("hex", |s| s),
+        DUMP_MODES,
+    )?;
+    let arch = keyword(
+        "arch",
+        matches.get_one::<String>("arch").map_or("x86_64", |s| s),
+        DUMP_ARCHS,
+    )?;
+    let json = matches.contains_id("json");
+    let data = read(path, offset, length)?;
+    if kind.eq("asm") {
+        dump_asm(&data, offset, arch, json)
+    } else {
+        dump_elements(&data, offset, kind, mode, json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scratch::Scratch;
+    use serde_json::Value;
+
+    fn dump_file(name: &str, data: &[u8], args: &[&str]) -> Result<String, Box<dyn Error>> {
+        let file = Scratch::with(name, data);
+        let path = file.path();
+        let mut argv = vec!["rsreit", "dump", &path];
+        argv.extend_from_slice(args);
+        let matches = crate::cli().try_get_matches_from(argv)?;
+        match matches.subcommand() {
+            Some(("dump", sub_matches)) => dump(sub_matches),
+            _ => Err("no dump subcommand".into()),
+        }
+    }
+
+    #[test]
+    fn elements_dump_as_json() {
+        let data = [0x34, 0x12, 0xff, 0xff, 0x01];
+        let out = dump_file(
+            "elements",
+            &data,
+            &["--kind", "word", "--mode", "dec", "--json"],
+        );
+        let json: Value = serde_json::from_str(&out.unwrap()).unwrap();
+        let entries = json.as_array().unwrap();
+        // the odd last byte does not make a word
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0]["offset"], 0);
+        assert_eq!(entries[0]["bytes"], "3412");
+        assert_eq!(entries[0]["decoded"], "04660");
+        assert_eq!(entries[1]["offset"], 2);
+        assert_eq!(entries[1]["decoded"], "65535");
+
+        let out = dump_file("elements-text", &data, &["--offset", "2", "--length", "2"]);
+        assert_eq!(out.unwrap(), "0x00000002  ff ff\n");
+    }
+
+    #[test]
+    fn asm_dumps_as_json() {
+        let data = [0x90, 0xb8, 0x78, 0x56, 0x34, 0x12, 0xc3];
+        let out = dump_file("asm", &data, &["--kind", "asm", "--offset", "1", "--json"]);
+        let json: Value = serde_json::from_str(&out.unwrap()).unwrap();
+        let entries = json.as_array().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0]["offset"], 1);
+        assert_eq!(entries[0]["bytes"], "b878563412");
+        assert!(entries[0]["text"].as_str().unwrap().starts_with("mov"));
+        assert_eq!(entries[1]["offset"], 6);
+        assert_eq!(entries[1]["text"], "ret");
+        assert_eq!(entries[0].as_object().unwrap().len(), 3);
+
+        let err = dump_file("asm-kind", &data, &["--kind", "float"]).unwrap_err();
+        assert!(err.to_string().starts_with("unknown kind float"));
+    }
+}
@@ -0,0 +1,368 @@
+use std::io;
+use std::os::unix::prelude::FileExt;
+
+const MAX_ENTRIES: u64 = 4096;
+const PT_LOAD: u32 = 1;
+const SHT_SYMTAB: u32 = 2;
+const SHT_NOBITS: u32 = 8;
+const SHT_DYNSYM: u32 = 11;
+const MAX_SYMBOLS: u64 = 1 << 20;
+
+pub struct Segment {
+    pub kind: u32,
+    pub flags: u32,
+    pub offset: u64,
+    pub vaddr: u64,
+    pub filesz: u64,
+    pub memsz: u64,
+}
+
+pub struct Section {
+    pub name: String,
+    pub kind: u32,
+    pub addr: u64,
+    pub offset: u64,
+    pub size: u64,
+    pub link: u32,
+}
+
+pub struct Symbol {
+    pub name: String,
+    pub value: u64,
+}
+
+pub struct Elf {
+    pub class64: bool,
+    pub little_endian: bool,
+    pub machine: u16,
+    pub entry: u64,
+    pub segments: Vec<Segment>,
+    pub sections: Vec<Section>,
+    pub symbols: Vec<Symbol>,
+}
+
+struct Reader<'a> {
+    file: &'a std::fs::File,
+    size: u64,
+    little_endian: bool,
+}
+
+impl<'a> Reader<'a> {
+    // sizes come from the file, so they are checked before anything is allocated
+    fn bytes(&self, offset: u64, len: usize) -> io::Result<Vec<u8>> {
+        match offset.checked_add(len as u64) {
+            Some(end) if end <= self.size => {}
+            _ => return Err(invalid("ELF data past end of file")),
+        }
+        let mut buf = vec![0u8; len];
+        self.file.read_exact_at(&mut buf, offset)?;
+        Ok(buf)
+    }
+
+    fn uint(&self, bytes: &[u8], at: usize, size: usize) -> u64 {
+        let mut value = 0u64;
+        for i in 0..size {
+            let b = if self.little_endian {
+                bytes[at + size - 1 - i]
+            } else {
+                bytes[at + i]
+            };
+            value = (value << 8) | b as u64;
+        }
+        value
+    }
+}
+
+fn string_at(table: &[u8], start: usize) -> String {
+    if start >= table.len() {
+        return String::new();
+    }
+    let end = table[start..]
+        .iter()
+        .position(|&b| b == 0)
+        .map_or(table.len(), |p| start + p);
+    String::from_utf8_lossy(&table[start..end]).to_string()
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+// offset of the i-th entry of a header table, the table offset comes from the file
+fn entry_offset(table: u64, i: u64, entsize: u64) -> io::Result<u64> {
+    i.checked_mul(entsize)
+        .and_then(|delta| table.checked_add(delta))
+        .ok_or_else(|| invalid("ELF header table past end of file"))
+}
+
+impl Elf {
+    pub fn parse(path: &str) -> io::Result<Elf> {
+        let file = std::fs::File::open(path)?;
+        let mut ident = [0u8; 16];
+        file.read_exact_at(&mut ident, 0)
+            .map_err(|_| invalid("file too short for ELF header"))?;
+        if !ident[0..4].eq(b"\x7fELF") {
+            return Err(invalid("not an ELF file"));
+        }
+        let class64 = match ident[4] {
+            1 => false,
+            2 => true,
+            _ => return Err(invalid("unknown ELF class")),
+        };
+        let little_endian = match ident[5] {
+            1 => true,
+            2 => false,
+            _ => return Err(invalid("unknown ELF data encoding")),
+        };
+        let r = Reader {
+            file: &file,
+            size: file.metadata()?.len(),
+            little_endian,
+        };
+        let w = if class64 { 8 } else { 4 };
+        let hdr = r.bytes(0, if class64 { 64 } else { 52 })?;
+        let machine = r.uint(&hdr, 18, 2) as u16;
+        let entry = r.uint(&hdr, 24, w);
+        let phoff = r.uint(&hdr, 24 + w, w);
+        let shoff = r.uint(&hdr, 24 + 2 * w, w);
+        let base = 24 + 3 * w + 4;
+        let phentsize = r.uint(&hdr, base + 2, 2);
+        let phnum = r.uint(&hdr, base + 4, 2);
+        let shentsize = r.uint(&hdr, base + 6, 2);
+        let shnum = r.uint(&hdr, base + 8, 2);
+        let shstrndx = r.uint(&hdr, base + 10, 2);
+        if phnum > MAX_ENTRIES || shnum > MAX_ENTRIES {
+            return Err(invalid("too many ELF headers"));
+        }
+
+        let mut segments = Vec::new();
+        if phoff != 0 && phentsize >= if class64 { 56 } else { 32 } {
+            for i in 0..phnum {
+                let ph = r.bytes(entry_offset(phoff, i, phentsize)?, phentsize as usize)?;
+                let segment = if class64 {
+                    Segment {
+                        kind: r.uint(&ph, 0, 4) as u32,
+                        flags: r.uint(&ph, 4, 4) as u32,
+                        offset: r.uint(&ph, 8, 8),
+                        vaddr: r.uint(&ph, 16, 8),
+                        filesz: r.uint(&ph, 32, 8),
+                        memsz: r.uint(&ph, 40, 8),
+                    }
+                } else {
+                    Segment {
+                        kind: r.uint(&ph, 0, 4) as u32,
+                        offset: r.uint(&ph, 4, 4),
+                        vaddr: r.uint(&ph, 8, 4),
+                        filesz: r.uint(&ph, 16, 4),
+                        memsz: r.uint(&ph, 20, 4),
+                        flags: r.uint(&ph, 24, 4) as u32,
+                    }
+                };
+                segments.push(segment);
+            }
+        }
+
+        let mut sections = Vec::new();
+        let mut names = Vec::new();
+        if shoff != 0 && shentsize >= if class64 { 64 } else { 40 } {
+            for i in 0..shnum {
+                let sh = r.bytes(entry_offset(shoff, i, shentsize)?, shentsize as usize)?;
+                names.push(r.uint(&sh, 0, 4));
+                sections.push(Section {
+                    name: String::new(),
+                    kind: r.uint(&sh, 4, 4) as u32,
+                    addr: r.uint(&sh, 8 + w, w),
+                    offset: r.uint(&sh, 8 + 2 * w, w),
+                    size: r.uint(&sh, 8 + 3 * w, w),
+                    link: r.uint(&sh, 8 + 4 * w, 4) as u32,
+                });
+            }
+        }
+        if (shstrndx as usize) < sections.len() {
+            let strtab = &sections[shstrndx as usize];
+            let strtab = r
+                .bytes(strtab.offset, strtab.size as usize)
+                .unwrap_or_default();
+            for (section, name) in sections.iter_mut().zip(names) {
+                section.name = string_at(&strtab, name as usize);
+            }
+        }
+
+        let mut symbols = Vec::new();
+        let entsize = if class64 { 24 } else { 16 };
+        for section in &sections {
+            if section.kind != SHT_SYMTAB && section.kind != SHT_DYNSYM {
+                continue;
+            }
+            let strtab = match sections.get(section.link as usize) {
+                Some(strtab) => r
+                    .bytes(strtab.offset, strtab.size as usize)
+                    .unwrap_or_default(),
+                None => continue,
+            };
+            let count = std::cmp::min(section.size / entsize, MAX_SYMBOLS);
+            let table = r
+                .bytes(section.offset, (count * entsize) as usize)
+                .unwrap_or_default();
+            for sym in table.chunks_exact(entsize as usize) {
+                let name = string_at(&strtab, r.uint(sym, 0, 4) as usize);
+                if name.is_empty() {
+                    continue;
+                }
+                let value = if class64 {
+                    r.uint(sym, 8, 8)
+                } else {
+                    r.uint(sym, 4, 4)
+                };
+                symbols.push(Symbol { name, value });
+            }
+        }
+
+        Ok(Elf {
+            class64,
+            little_endian,
+            machine,
+            entry,
+            segments,
+            sections,
+            symbols,
+        })
+    }
+
+    pub fn vaddr_to_offset(&self, vaddr: u64) -> Option<u64> {
+        for segment in &self.segments {
+            if segment.kind == PT_LOAD
+                && vaddr >= segment.vaddr
+                && vaddr - segment.vaddr < segment.filesz
+            {
+                return Some(segment.offset + (vaddr - segment.vaddr));
+            }
+        }
+        for section in &self.sections {
+            if section.addr != 0 && vaddr >= section.addr && vaddr - section.addr < section.size {
+                return Some(section.offset + (vaddr - section.addr));
+            }
+        }
+        None
+    }
+
+    // file offset of a section, or else of a symbol, with that name
+    pub fn resolve(&self, name: &str) -> Option<u64> {
+        if let Some(section) = self
+            .sections
+            .iter()
+            .find(|s| s.name.eq(name) && s.kind != SHT_NOBITS)
+        {
+            return Some(section.offset);
+        }
+        let symbol = self.symbols.iter().find(|s| s.name.eq(name))?;
+        self.vaddr_to_offset(symbol.value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scratch::Scratch;
+
+    const TEXT: u64 = 0x100;
+    const SHSTRTAB: u64 = 0x120;
+    const STRTAB: u64 = 0x150;
+    const SYMTAB: u64 = 0x160;
+    const SHDRS: u64 = 0x200;
+    const VADDR: u64 = 0x400000;
+
+    fn put(image: &mut [u8], at: u64, value: u64, size: usize) {
+        let at = at as usize;
+        image[at..at + size].copy_from_slice(&value.to_le_bytes()[..size]);
+    }
+
+    fn section(image: &mut [u8], i: u64, name: u64, kind: u64, at: (u64, u64), link: u64) {
+        let sh = SHDRS + i * 64;
+        put(image, sh, name, 4);
+        put(image, sh + 4, kind, 4);
+        put(image, sh + 16, VADDR + at.0, 8);
+        put(image, sh + 24, at.0, 8);
+        put(image, sh + 32, at.1, 8);
+        put(image, sh + 40, link, 4);
+    }
+
+    // x86_64 image with one load segment, .text, a main symbol and both string tables
+    fn image(strtab_size: u64) -> Vec<u8> {
+        let mut image = vec![0u8; (SHDRS + 5 * 64) as usize];
+        image[..8].copy_from_slice(b"\x7fELF\x02\x01\x01\x00");
+        put(&mut image, 18, 0x3e, 2);
+        put(&mut image, 24, VADDR + TEXT, 8);
+        put(&mut image, 32, 64, 8);
+        put(&mut image, 40, SHDRS, 8);
+        put(&mut image, 54, 56, 2);
+        put(&mut image, 56, 1, 2);
+        put(&mut image, 58, 64, 2);
+        put(&mut image, 60, 5, 2);
+        put(&mut image, 62, 2, 2);
+        put(&mut image, 64, PT_LOAD as u64, 4);
+        put(&mut image, 64 + 16, VADDR, 8);
+        put(&mut image, 64 + 32, SHDRS, 8);
+        put(&mut image, 64 + 40, SHDRS, 8);
+        let names = b"\0.text\0.shstrtab\0.symtab\0.strtab\0";
+        image[SHSTRTAB as usize..SHSTRTAB as usize + names.len()].copy_from_slice(names);
+        image[STRTAB as usize..STRTAB as usize + 6].copy_from_slice(b"\0main\0");
+        put(&mut image, SYMTAB + 24, 1, 4);
+        put(&mut image, SYMTAB + 24 + 8, VADDR + TEXT + 8, 8);
+        section(&mut image, 1, 1, 1, (TEXT, 0x10), 0);
+        section(&mut image, 2, 7, 3, (SHSTRTAB, strtab_size), 0);
+        section(&mut image, 3, 17, SHT_SYMTAB as u64, (SYMTAB, 48), 4);
+        section(&mut image, 4, 25, 3, (STRTAB, strtab_size), 0);
+        image
+    }
+
+    fn parse(name: &str, image: &[u8]) -> io::Result<Elf> {
+        let file = Scratch::with(name, image);
+        Elf::parse(&file.path())
+    }
+
+    #[test]
+    fn parses_sections() {
+        let elf = parse("elf-sections", &image(0x30)).unwrap();
+        assert!(elf.class64 && elf.little_endian);
+        assert_eq!(elf.machine, 0x3e);
+        let names: Vec<&str> = elf.sections.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["", ".text", ".shstrtab", ".symtab", ".strtab"]);
+    }
+
+    #[test]
+    fn resolves_sections_and_symbols() {
+        let elf = parse("elf-resolve", &image(0x30)).unwrap();
+        assert_eq!(elf.resolve(".text"), Some(TEXT));
+        assert_eq!(elf.resolve("main"), Some(TEXT + 8));
+        assert_eq!(elf.resolve("missing"), None);
+    }
+
+    #[test]
+    fn oversized_symbol_names_are_skipped() {
+        let mut image = image(0x30);
+        section(&mut image, 4, 25, 3, (STRTAB, u64::MAX >> 1), 0);
+        let elf = parse("elf-symbols", &image).unwrap();
+        assert!(elf.symbols.is_empty());
+        assert_eq!(elf.resolve(".text"), Some(TEXT));
+        assert_eq!(elf.resolve("main"), None);
+    }
+
+    #[test]
+    fn oversized_string_table_is_skipped() {
+        let elf = parse("elf-oversized", &image(u64::MAX >> 1)).unwrap();
+        assert_eq!(elf.sections.len(), 5);
+        assert!(elf.sections.iter().all(|s| s.name.is_empty()));
+        assert!(elf.symbols.is_empty());
+    }
+
+    #[test]
+    fn header_tables_past_the_address_space_fail() {
+        let mut program = image(0x30);
+        put(&mut program, 32, u64::MAX - 8, 8);
+        assert!(parse("elf-phoff", &program).is_err());
+        let mut sections = image(0x30);
+        put(&mut sections, 40, u64::MAX - 64, 8);
+        assert!(parse("elf-shoff", &sections).is_err());
+    }
+}
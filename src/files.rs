@@ -1,40 +1,147 @@
 use crate::block::Block;
 use crate::hits::HHits;
+use crate::recent;
 use crate::tabs::Tabs;
 use crate::undo::UndoRedo;
+use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 use std::fs::OpenOptions;
 use std::io;
 use std::io::prelude::*;
+use std::io::Cursor;
 use std::io::SeekFrom;
 use std::ops::Bound::Excluded;
 use std::ops::Bound::Included;
 use std::os::unix::prelude::FileExt;
+use std::time::SystemTime;
 
-#[derive(Clone, Eq, PartialEq)]
+// where the bytes of a file come from, path is only a label for memory buffers
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Source {
+    Disk,
+    Memory(Vec<u8>),
+}
+
+pub enum Reader<'a> {
+    Disk(std::fs::File),
+    Memory(Cursor<&'a [u8]>),
+}
+
+impl Source {
+    // opens the source for reading and returns it with its current length
+    pub fn reader(&self, path: &str) -> io::Result<(Reader<'_>, u64)> {
+        match self {
+            Source::Disk => {
+                let file = std::fs::File::open(path)?;
+                let len = file.metadata()?.len();
+                Ok((Reader::Disk(file), len))
+            }
+            Source::Memory(data) => Ok((Reader::Memory(Cursor::new(data)), data.len() as u64)),
+        }
+    }
+}
+
+impl<'a> Read for Reader<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Reader::Disk(file) => file.read(buf),
+            Reader::Memory(cursor) => cursor.read(buf),
+        }
+    }
+}
+
+impl<'a> Seek for Reader<'a> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match self {
+            Reader::Disk(file) => file.seek(pos),
+            Reader::Memory(cursor) => cursor.seek(pos),
+        }
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct File {
     pub path: String,
+    #[serde(skip)]
     pub size: u64,
     pub block: Block,
     pub patch: BTreeMap<u64, Vec<u8>>,
+    #[serde(skip)]
     pub undo: UndoRedo,
+    #[serde(skip)]
     pub redo: UndoRedo,
     pub hhits: HHits,
+    pub image_base: u64,
+    #[serde(skip)]
+    pub jumps: Vec<u64>,
+    #[serde(skip)]
+    pub kind: Option<&'static str>,
+    #[serde(default)]
+    pub watch: bool,
+    #[serde(skip)]
+    pub stamp: Option<(u64, SystemTime)>,
+    // offset tail mode last scrolled to, any other offset means the user scrolled away
+    #[serde(skip)]
+    pub tail: Option<u64>,
+    #[serde(default = "default_source")]
+    pub source: Source,
+    // named positions keyed by offset, so the views can look up the rows they draw
+    #[serde(default)]
+    pub bookmarks: BTreeMap<u64, String>,
 }
 
-#[derive(Eq, PartialEq)]
+fn default_source() -> Source {
+    Source::Disk
+}
+
+impl File {
+    pub fn is_memory(&self) -> bool {
+        matches!(self.source, Source::Memory(_))
+    }
+
+    // a fresh edit starts its own undo group and makes anything undone unreachable
+    pub fn begin_edit(&mut self) {
+        self.undo.begin();
+        self.redo.clear();
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Files {
     pub files: Vec<File>,
     pub index: usize,
+    pub block_size: u64,
+    pub offset: u64,
+    #[serde(skip, default = "default_undo_limit")]
+    pub undo_limit: usize,
+}
+
+fn default_undo_limit() -> usize {
+    DEFAULT_UNDO_LIMIT
+}
+
+pub struct WriteSummary {
+    pub entries: usize,
+    pub bytes: u64,
+    pub ranges: Vec<(u64, u64)>,
+    pub written: Vec<(u64, Vec<u8>)>,
 }
 
 const WRITE_BLOCK: u64 = 2048u64;
+// patch entries never exceed this size, so only this much before a block needs checking
+pub const MAX_PATCH_SIZE: u64 = WRITE_BLOCK;
+pub const MAX_BLOCK_SIZE: u64 = 1u64 << 26;
+// bytes of undo history kept per file, each edit stores its bytes before and after
+pub const DEFAULT_UNDO_LIMIT: usize = 1 << 26;
 
 impl Files {
     pub fn default() -> Files {
         Files {
             files: Vec::new(),
             index: 0,
+            block_size: 2048u64,
+            offset: 0u64,
+            undo_limit: DEFAULT_UNDO_LIMIT,
         }
     }
 
@@ -54,89 +161,360 @@ impl Files {
         }
     }
 
-    fn new(path: String) -> File {
+    fn new(path: String, block_size: u64, offset: u64, undo_limit: usize) -> File {
+        let mut block = Block::new(block_size as usize);
+        block.offset = offset;
         File {
             path,
             size: 0u64,
-            block: Block::new(2048usize),
+            block,
             patch: BTreeMap::new(),
-            undo: UndoRedo::new(),
-            redo: UndoRedo::new(),
+            undo: UndoRedo::new(undo_limit),
+            redo: UndoRedo::new(undo_limit),
             hhits: HHits::default(),
+            image_base: 0u64,
+            jumps: Vec::new(),
+            kind: None,
+            watch: false,
+            stamp: None,
+            tail: None,
+            source: Source::Disk,
+            bookmarks: BTreeMap::new(),
         }
     }
 
     pub fn add(&mut self, path: String, tabs: &mut Tabs) {
-        self.files.push(Self::new(path));
-        tabs.add(String::from(format!("tab{}", tabs.tabs.len())));
+        let _ = recent::remember(&path);
+        self.files.push(Self::new(
+            path,
+            self.block_size,
+            self.offset,
+            self.undo_limit,
+        ));
+        tabs.add(
+            String::from(format!("tab{}", tabs.tabs.len())),
+            self.block_size,
+            self.offset,
+        );
+        if let Some(tab) = tabs.tabs.last_mut() {
+            tab.fileitem_index = self.files.len() - 1;
+        }
     }
 
-    pub fn current(&mut self, index: usize) -> &mut File {
-        &mut self.files[index]
+    pub fn add_memory(&mut self, label: String, data: Vec<u8>, tabs: &mut Tabs) {
+        let mut fi = Self::new(label, self.block_size, 0, self.undo_limit);
+        fi.size = data.len() as u64;
+        fi.source = Source::Memory(data);
+        self.files.push(fi);
+        tabs.add(
+            String::from(format!("tab{}", tabs.tabs.len())),
+            self.block_size,
+            0,
+        );
+        if let Some(tab) = tabs.tabs.last_mut() {
+            tab.fileitem_index = self.files.len() - 1;
+        }
     }
 
-    pub fn current_path(&mut self, tabs: &mut Tabs) -> &String {
-        let file_index = tabs.file_index();
-        &self.files[file_index].path
+    pub fn set_undo_limit(&mut self, limit: usize) {
+        self.undo_limit = limit;
+        for fi in self.files.iter_mut() {
+            fi.undo.set_limit(limit);
+            fi.redo.set_limit(limit);
+        }
     }
 
-    pub fn read_block(
-        file: &mut std::fs::File,
+    pub fn current(&mut self, index: usize) -> &mut File {
+        &mut self.files[index]
+    }
+
+    pub fn read_block<R: Read + Seek>(
+        file: &mut R,
         size: u64,
         offset: u64,
         len: u64,
         buffer: &mut Vec<u8>,
-    ) -> io::Result<()> {
+    ) -> io::Result<usize> {
         let mut nb_read = 0;
+        buffer.resize(size as usize, 0);
         if offset < len {
             file.seek(SeekFrom::Start(offset))?;
-            buffer.resize(size.try_into().unwrap(), 0);
             let mut handle = file.take(size);
-            nb_read = handle.read(buffer)?;
+            // a single read may come back short, keep going until the window is full
+            while nb_read < size as usize {
+                match handle.read(&mut buffer[nb_read..]) {
+                    Ok(0) => break,
+                    Ok(n) => nb_read += n,
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                    Err(e) => return Err(e),
+                }
+            }
         }
         buffer[nb_read..size as usize].fill(0xFF);
-        Ok(())
+        Ok(nb_read)
     }
 
-    pub fn write(&mut self, index: usize) -> io::Result<()> {
+    pub fn stamp(path: &str) -> io::Result<(u64, SystemTime)> {
+        let metadata = std::fs::metadata(path)?;
+        Ok((metadata.len(), metadata.modified()?))
+    }
+
+    pub fn read_range(&mut self, index: usize, offset: u64, size: u64) -> io::Result<Vec<u8>> {
+        Ok(self.read_block_at(index, offset, size)?.buffer)
+    }
+
+    pub fn read_block_at(&mut self, index: usize, offset: u64, size: u64) -> io::Result<Block> {
+        let fi = self.current(index);
+        let mut block = Block::new(size as usize);
+        block.offset = offset;
+        let (mut reader, len) = fi.source.reader(&fi.path)?;
+        let nb_read = Self::read_block(&mut reader, size, offset, len, &mut block.buffer)?;
+        block.buffer.truncate(nb_read);
+        block.data_len = nb_read;
+        block.source.clone_from(&block.buffer);
+        Self::do_apply_patch(&mut block, &fi.patch);
+        Ok(block)
+    }
+
+    pub fn write(&mut self, index: usize) -> io::Result<WriteSummary> {
         let mut block = Block::new(2048usize);
         let fi = self.current(index);
+        if fi.is_memory() {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("{} is an in-memory buffer", fi.path),
+            ));
+        }
         let path = fi.path.clone();
         let mut file = OpenOptions::new()
             .read(true)
             .write(true)
             .create(true)
+            .truncate(false)
             .open(&path)?;
         let len = std::fs::metadata(path)?.len();
-        let r = fi.patch.range((Included(&0), Excluded(&fi.size)));
-        let mut next_offset;
-        let mut prev_offset = 0u64;
+        // an empty entry changes nothing, a loaded session may still hold one
+        let r = fi
+            .patch
+            .range((Included(&0), Excluded(&fi.size)))
+            .filter(|(_, bytes)| !bytes.is_empty());
+        let mut summary = WriteSummary {
+            entries: r.clone().count(),
+            bytes: 0,
+            ranges: Vec::new(),
+            written: Vec::new(),
+        };
+        // blocks below this one were already written for an earlier entry
+        let mut next_block = 0u64;
         for (offset, bytes) in r {
-            let at = offset & !(WRITE_BLOCK - 1);
-            let size = ((bytes.len() as u64 - 1) | (WRITE_BLOCK - 1)) + 1;
-
-            next_offset = at + size as u64;
-            if prev_offset < next_offset {
-                Self::read_block(&mut file, size, at, len, &mut block.buffer)?;
+            let mut at = std::cmp::max(offset & !(WRITE_BLOCK - 1), next_block);
+            let end = offset + bytes.len() as u64;
+            while at < end {
+                Self::read_block(&mut file, WRITE_BLOCK, at, len, &mut block.buffer)?;
                 block.offset = at;
+                block.source.clone_from(&block.buffer);
                 Files::do_apply_patch(&mut block, &fi.patch);
-                let bsize = std::cmp::min(size, len - at) as usize;
-                file.write_at(&block.buffer[0..bsize], at)?;
-                prev_offset = next_offset;
+                let bsize = std::cmp::min(WRITE_BLOCK, len.saturating_sub(at)) as usize;
+                let mut changed = false;
+                for i in 0..bsize {
+                    if block.source[i] == block.buffer[i] {
+                        continue;
+                    }
+                    let pos = at + i as u64;
+                    changed = true;
+                    summary.bytes += 1;
+                    match summary.ranges.last_mut() {
+                        Some((_, end)) if *end == pos => *end = pos + 1,
+                        _ => summary.ranges.push((pos, pos + 1)),
+                    }
+                }
+                if changed {
+                    file.write_at(&block.buffer[0..bsize], at)?;
+                    summary.written.push((at, block.buffer[0..bsize].to_vec()));
+                }
+                at += WRITE_BLOCK;
+                next_block = at;
             }
         }
         fi.patch.clear();
-        Ok(())
+        fi.block.prev_size = 0;
+        Ok(summary)
+    }
+
+    pub fn verify(&mut self, index: usize, summary: &WriteSummary) -> io::Result<Vec<(u64, u64)>> {
+        let file = std::fs::File::open(&self.current(index).path)?;
+        let mut mismatches: Vec<(u64, u64)> = Vec::new();
+        for (at, expected) in &summary.written {
+            let mut buffer = vec![0u8; expected.len()];
+            let mut nb_read = 0;
+            while nb_read < buffer.len() {
+                let n = file.read_at(&mut buffer[nb_read..], at + nb_read as u64)?;
+                if n == 0 {
+                    break;
+                }
+                nb_read += n;
+            }
+            for i in 0..expected.len() {
+                if i < nb_read && buffer[i] == expected[i] {
+                    continue;
+                }
+                let pos = at + i as u64;
+                match mismatches.last_mut() {
+                    Some((_, end)) if *end == pos => *end = pos + 1,
+                    _ => mismatches.push((pos, pos + 1)),
+                }
+            }
+        }
+        Ok(mismatches)
+    }
+
+    pub fn prune_patch(fi: &mut File) {
+        let min = fi.block.offset;
+        let max = fi.block.offset + fi.block.source.len() as u64;
+        let source = &fi.block.source;
+        fi.patch.retain(|key, value| {
+            let end = key + value.len() as u64;
+            *key < min
+                || end > max
+                || source[(key - min) as usize..(end - min) as usize] != value[..]
+        });
+    }
+
+    // the editor never makes empty entries or ones crossing a MAX_PATCH_SIZE boundary,
+    // but a session file may hold them
+    pub fn split_patch(patch: &mut BTreeMap<u64, Vec<u8>>) {
+        for (offset, bytes) in std::mem::take(patch) {
+            if offset.checked_add(bytes.len() as u64).is_none() {
+                continue;
+            }
+            let mut at = offset;
+            let mut rest = &bytes[..];
+            while !rest.is_empty() {
+                let split = std::cmp::min(MAX_PATCH_SIZE - at % MAX_PATCH_SIZE, rest.len() as u64);
+                patch.insert(at, rest[..split as usize].to_vec());
+                at += split;
+                rest = &rest[split as usize..];
+            }
+        }
     }
 
     pub fn do_apply_patch(block: &mut Block, patch: &BTreeMap<u64, Vec<u8>>) {
         let min = block.offset;
-        let max = block.offset + block.size;
-        let r = patch.range((Included(&min), Excluded(&max)));
+        let max = block.offset + block.buffer.len() as u64;
+        let r = patch.range((
+            Included(&min.saturating_sub(MAX_PATCH_SIZE)),
+            Excluded(&max),
+        ));
         for (key, value) in r {
-            let bmin = (key - min) as usize;
-            let bmax = bmin + value.len() as usize;
-            block.buffer.splice(bmin..bmax, value.clone());
+            let end = key + value.len() as u64;
+            if end <= min {
+                continue;
+            }
+            let start = std::cmp::max(*key, min);
+            let stop = std::cmp::min(end, max);
+            let bytes = &value[(start - key) as usize..(stop - key) as usize];
+            block.buffer[(start - min) as usize..(stop - min) as usize].copy_from_slice(bytes);
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scratch::Scratch;
+
+    // a 6000 byte file on disk, opened without reading any block
+    fn disk_files(name: &str) -> (Files, Vec<u8>, Scratch) {
+        let data: Vec<u8> = (0..6000u32).map(|i| (i % 251) as u8).collect();
+        let file = Scratch::with(name, &data);
+        let mut files = Files::default();
+        let mut fi = Files::new(file.path(), 2048, 0, 0);
+        fi.size = data.len() as u64;
+        files.files.push(fi);
+        (files, data, file)
+    }
+
+    #[test]
+    fn write_skips_unchanged_blocks() {
+        let (mut files, mut data, file) = disk_files("write");
+        let fi = files.current(0);
+        fi.patch.insert(10, vec![0x55, data[11]]);
+        fi.patch.insert(100, vec![data[100]]);
+        fi.patch.insert(2100, vec![data[2100]]);
+        fi.patch.insert(4100, vec![0xaa, 0xbb]);
+        fi.patch.insert(6000, vec![0xcc]);
+        let summary = files.write(0).unwrap();
+        assert_eq!(summary.entries, 4);
+        assert_eq!(summary.bytes, 3);
+        assert_eq!(summary.ranges, [(10, 11), (4100, 4102)]);
+        let written: Vec<(u64, usize)> = summary
+            .written
+            .iter()
+            .map(|(at, bytes)| (*at, bytes.len()))
+            .collect();
+        assert_eq!(written, [(0, 2048), (4096, 1904)]);
+        assert!(files.current(0).patch.is_empty());
+
+        data[10] = 0x55;
+        data[4100] = 0xaa;
+        data[4101] = 0xbb;
+        assert_eq!(std::fs::read(&file).unwrap(), data);
+    }
+
+    #[test]
+    fn write_covers_every_block_of_an_entry() {
+        // the file never holds bytes above 250, so every patched byte differs
+        let (mut files, mut data, file) = disk_files("write-straddle");
+        let fi = files.current(0);
+        fi.patch.insert(100, Vec::new());
+        fi.patch.insert(2040, vec![0xfb; 16]);
+        fi.patch.insert(3000, vec![0xfc; 2100]);
+        let summary = files.write(0).unwrap();
+        assert_eq!(summary.entries, 2);
+        assert_eq!(summary.bytes, 16 + 2100);
+        assert_eq!(summary.ranges, [(2040, 2056), (3000, 5100)]);
+        let written: Vec<u64> = summary.written.iter().map(|(at, _)| *at).collect();
+        assert_eq!(written, [0, 2048, 4096]);
+
+        data[2040..2056].fill(0xfb);
+        data[3000..5100].fill(0xfc);
+        assert_eq!(std::fs::read(&file).unwrap(), data);
+    }
+
+    #[test]
+    fn verify_reports_mismatches() {
+        let (mut files, _, file) = disk_files("verify");
+        let fi = files.current(0);
+        fi.patch.insert(10, vec![0x55, 0x66]);
+        fi.patch.insert(4100, vec![0xaa]);
+        let summary = files.write(0).unwrap();
+        assert!(files.verify(0, &summary).unwrap().is_empty());
+
+        let disk = OpenOptions::new().write(true).open(&file).unwrap();
+        disk.write_at(&[0, 0], 11).unwrap();
+        disk.write_at(&[0], 4100).unwrap();
+        disk.set_len(5000).unwrap();
+        let mismatches = files.verify(0, &summary).unwrap();
+        assert_eq!(mismatches, [(11, 13), (4100, 4101), (5000, 6000)]);
+    }
+
+    #[test]
+    fn memory_buffers_read_edit_and_refuse_writes() {
+        let mut files = Files::default();
+        let mut tabs = Tabs::default();
+        let data: Vec<u8> = (0u8..100).collect();
+        files.add_memory("stdin".to_string(), data.clone(), &mut tabs);
+        assert_eq!(tabs.tabs.len(), 1);
+        assert!(files.current(0).is_memory());
+        assert_eq!(files.read_range(0, 90, 32).unwrap(), &data[90..]);
+
+        files.current(0).patch.insert(95, vec![0xaa, 0xbb]);
+        let block = files.read_block_at(0, 94, 4).unwrap();
+        assert_eq!(block.buffer, [94, 0xaa, 0xbb, 97]);
+        assert_eq!(block.source, [94, 95, 96, 97]);
+
+        let err = files.write(0).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+        assert_eq!(files.current(0).patch.len(), 1);
+    }
+}
@@ -1,18 +1,35 @@
+use std::collections::VecDeque;
+use std::fs;
+use std::fs::OpenOptions;
+use std::io;
+use std::io::Write;
 use tui::style::Style;
 use tui::text::Span;
 use tui::text::Spans;
 
+pub const DEFAULT_MAX_LINES: usize = 100000;
+
 #[derive(Clone)]
 pub struct History<'a> {
-    pub history: Vec<Spans<'a>>,
+    pub history: VecDeque<Spans<'a>>,
     pub scroll: usize,
+    pub log: Option<String>,
+    pub max_lines: usize,
+    pub query: String,
+    pub searching: bool,
+    pub found: bool,
 }
 
 impl<'a> History<'a> {
     pub fn default() -> History<'a> {
         History {
-            history: Vec::new(),
+            history: VecDeque::new(),
             scroll: 0,
+            log: None,
+            max_lines: DEFAULT_MAX_LINES,
+            query: String::new(),
+            searching: false,
+            found: true,
         }
     }
 
@@ -21,32 +38,175 @@ impl<'a> History<'a> {
         let mut buffer = Vec::new();
         line.push(Span::styled(msg, style));
         buffer.push(Spans::from(line));
-        self.history.push(buffer[0].clone());
+        self.tee(&buffer);
+        self.history.push_back(buffer[0].clone());
+        self.trim();
     }
 
     pub fn add(&mut self, buffer: &Vec<Spans<'a>>) {
+        self.tee(buffer);
         for l in buffer {
-            self.history.push(l.clone());
+            self.history.push_back(l.clone());
         }
+        self.trim();
     }
 
-    pub fn scroll_up(&mut self, amount: usize) {
-        if self.scroll < self.history.len() {
-            self.scroll += amount;
+    pub fn set_max_lines(&mut self, max_lines: usize) {
+        self.max_lines = std::cmp::max(max_lines, 1);
+        self.trim();
+    }
+
+    fn trim(&mut self) {
+        if self.history.len() > self.max_lines {
+            let excess = self.history.len() - self.max_lines;
+            self.history.drain(..excess);
         }
-        if self.scroll > self.history.len() {
-            self.scroll = self.history.len() - 1;
+        if self.scroll >= self.history.len() {
+            self.scroll = self.history.len().saturating_sub(1);
         }
     }
 
-    pub fn scroll_down(&mut self, amount: usize) {
-        if self.scroll > amount {
-            self.scroll -= amount;
+    pub fn set_log(&mut self, path: String) -> io::Result<()> {
+        OpenOptions::new().create(true).append(true).open(&path)?;
+        self.log = Some(path);
+        Ok(())
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<usize> {
+        let mut text = String::new();
+        for l in &self.history {
+            text.push_str(&Self::plain_text(l));
+        }
+        fs::write(path, text)?;
+        Ok(self.history.len())
+    }
+
+    fn tee(&self, buffer: &[Spans]) {
+        if let Some(path) = &self.log {
+            let mut text = String::new();
+            for l in buffer {
+                text.push_str(&Self::plain_text(l));
+            }
+            let _ = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .and_then(|mut file| file.write_all(text.as_bytes()));
+        }
+    }
+
+    fn plain_text(line: &Spans) -> String {
+        let mut text = Self::line_text(line);
+        text.push('\n');
+        text
+    }
+
+    fn line_text(line: &Spans) -> String {
+        let mut text = String::new();
+        for span in &line.0 {
+            text.push_str(&span.content);
+        }
+        text
+    }
+
+    pub fn is_match(&self, line: &Spans) -> bool {
+        !self.query.is_empty() && Self::line_text(line).contains(&self.query)
+    }
+
+    // moves scroll so the next matching line in the given direction sits at the bottom of the view
+    pub fn find(&mut self, older: bool, include_current: bool) -> bool {
+        let len = self.history.len();
+        if self.query.is_empty() || len == 0 {
+            return false;
+        }
+        let current = len - 1 - std::cmp::min(self.scroll, len - 1);
+        let found = if older {
+            let end = if include_current {
+                current + 1
+            } else {
+                current
+            };
+            (0..end).rev().find(|i| self.is_match(&self.history[*i]))
         } else {
-            self.scroll = 0;
+            let start = if include_current {
+                current
+            } else {
+                current + 1
+            };
+            (start..len).find(|i| self.is_match(&self.history[*i]))
+        };
+        self.found = found.is_some();
+        if let Some(i) = found {
+            self.scroll = len - 1 - i;
         }
-        if self.scroll > self.history.len() {
-            self.scroll = self.history.len() - 1;
+        self.found
+    }
+
+    pub fn scroll_up(&mut self, amount: usize) {
+        self.scroll = std::cmp::min(
+            self.scroll.saturating_add(amount),
+            self.history.len().saturating_sub(1),
+        );
+    }
+
+    pub fn scroll_newest(&mut self) {
+        self.scroll = 0;
+    }
+
+    pub fn scroll_oldest(&mut self) {
+        self.scroll = self.history.len().saturating_sub(1);
+    }
+
+    pub fn scroll_down(&mut self, amount: usize) {
+        self.scroll = std::cmp::min(
+            self.scroll.saturating_sub(amount),
+            self.history.len().saturating_sub(1),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(history: &History) -> Vec<String> {
+        history.history.iter().map(History::line_text).collect()
+    }
+
+    #[test]
+    fn max_lines_drops_oldest() {
+        let mut history = History::default();
+        history.set_max_lines(3);
+        for i in 0..5 {
+            history.print(Style::default(), format!("line {}", i));
         }
+        assert_eq!(lines(&history), ["line 2", "line 3", "line 4"]);
+        history.add(&vec![Spans::from("a"), Spans::from("b")]);
+        assert_eq!(lines(&history), ["line 4", "a", "b"]);
+        history.scroll_oldest();
+        assert_eq!(history.scroll, 2);
+        history.set_max_lines(0);
+        assert_eq!(lines(&history), ["b"]);
+        assert_eq!(history.scroll, 0);
+    }
+
+    #[test]
+    fn empty_history_scrolls_in_place() {
+        let mut history = History::default();
+        history.scroll_up(5);
+        assert_eq!(history.scroll, 0);
+        history.scroll_down(5);
+        assert_eq!(history.scroll, 0);
+        history.scroll_oldest();
+        assert_eq!(history.scroll, 0);
+        history.query = "x".to_string();
+        assert!(!history.find(true, true));
+
+        history.print(Style::default(), "x".to_string());
+        history.print(Style::default(), "y".to_string());
+        history.scroll_up(usize::MAX);
+        assert_eq!(history.scroll, 1);
+        history.scroll_down(usize::MAX);
+        assert_eq!(history.scroll, 0);
     }
 }
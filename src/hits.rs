@@ -1,21 +1,25 @@
-#[derive(Clone, Eq, PartialEq)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Hits {
     pub hits: Vec<u64>,
     pub flag: String,
+    pub len: u64,
     pub selected: usize,
 }
 
-#[derive(Clone, Eq, PartialEq)]
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct HHits {
     pub hits: Vec<Hits>,
     pub selected: usize,
 }
 
 impl Hits {
-    pub fn new(flag: String) -> Hits {
+    pub fn new(flag: String, len: u64) -> Hits {
         Hits {
             hits: Vec::new(),
             flag,
+            len,
             selected: 0usize,
         }
     }
@@ -23,6 +27,11 @@ impl Hits {
     pub fn is_empty(&mut self) -> bool {
         self.hits.is_empty()
     }
+
+    pub fn contains(&self, start: u64, end: u64) -> bool {
+        let idx = self.hits.partition_point(|&h| h + self.len <= start);
+        idx < self.hits.len() && self.hits[idx] < end
+    }
 }
 
 impl HHits {
@@ -33,7 +42,63 @@ impl HHits {
         }
     }
 
-    pub fn add(&mut self, hits: Hits) {
-        self.hits.push(hits);
+    // a repeated search refreshes its group in place instead of adding a duplicate
+    pub fn add(&mut self, mut hits: Hits) {
+        match self.hits.iter().position(|h| h.flag == hits.flag) {
+            Some(index) => {
+                let selected = self.hits[index].selected;
+                if selected < hits.hits.len() {
+                    hits.selected = selected;
+                }
+                self.hits[index] = hits;
+                self.selected = index;
+            }
+            None => self.hits.push(hits),
+        }
+    }
+
+    pub fn remove(&mut self) -> Option<Hits> {
+        if self.selected >= self.hits.len() {
+            return None;
+        }
+        let hits = self.hits.remove(self.selected);
+        if self.selected >= self.hits.len() && self.selected > 0 {
+            self.selected -= 1;
+        }
+        Some(hits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hits(flag: &str, offsets: &[u64]) -> Hits {
+        let mut hits = Hits::new(flag.to_string(), 2);
+        hits.hits = offsets.to_vec();
+        hits
+    }
+
+    #[test]
+    fn repeated_search_refreshes_its_group() {
+        let mut hhits = HHits::default();
+        hhits.add(hits("AB", &[0, 8, 16]));
+        hhits.add(hits("CD", &[4]));
+        assert_eq!(hhits.selected, 0);
+        hhits.hits[0].selected = 2;
+
+        hhits.add(hits("AB", &[0, 8, 16, 24]));
+        assert_eq!(hhits.hits.len(), 2);
+        assert_eq!(hhits.hits[0].hits, [0, 8, 16, 24]);
+        assert_eq!(hhits.hits[0].selected, 2);
+        assert_eq!(hhits.hits[1].flag, "CD");
+        assert_eq!(hhits.selected, 0);
+
+        // a selection past the new hits starts over
+        hhits.add(hits("AB", &[8]));
+        assert_eq!(hhits.hits[0].selected, 0);
+        hhits.add(hits("CD", &[4, 12]));
+        assert_eq!(hhits.hits.len(), 2);
+        assert_eq!(hhits.selected, 1);
     }
 }
@@ -0,0 +1,151 @@
+const SECONDS_PER_DAY: i64 = 86400;
+// seconds between 1601-01-01 and 1970-01-01
+const FILETIME_EPOCH: i64 = 11644473600;
+const MAX_YEAR: i64 = 9999;
+
+// days since 1970-01-01 to year, month, day in the proleptic gregorian calendar
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+pub fn format_unix(secs: i64, millis: u32) -> Option<String> {
+    let days = secs.div_euclid(SECONDS_PER_DAY);
+    let time = secs.rem_euclid(SECONDS_PER_DAY);
+    let (year, month, day) = civil_from_days(days);
+    if !(1..=MAX_YEAR).contains(&year) {
+        return None;
+    }
+    Some(format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}.{:03} UTC",
+        year,
+        month,
+        day,
+        time / 3600,
+        time / 60 % 60,
+        time % 60,
+        millis
+    ))
+}
+
+pub fn unix_seconds(value: i64) -> Option<String> {
+    format_unix(value, 0)
+}
+
+pub fn unix_millis(value: i64) -> Option<String> {
+    format_unix(value.div_euclid(1000), value.rem_euclid(1000) as u32)
+}
+
+pub fn filetime(value: u64) -> Option<String> {
+    let secs = (value / 10_000_000) as i64 - FILETIME_EPOCH;
+    let millis = (value % 10_000_000 / 10_000) as u32;
+    format_unix(secs, millis)
+}
+
+// time in the low and date in the high 16 bits, as stored in FAT and ZIP headers
+pub fn dos_datetime(value: u32) -> Option<String> {
+    let time = value & 0xffff;
+    let date = value >> 16;
+    let (year, month, day) = (1980 + (date >> 9), (date >> 5) & 15, date & 31);
+    let (hour, minute, second) = (time >> 11, (time >> 5) & 63, (time & 31) * 2);
+    if !(1..=12).contains(&month) || day == 0 || hour > 23 || minute > 59 || second > 59 {
+        return None;
+    }
+    Some(format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        year, month, day, hour, minute, second
+    ))
+}
+
+// one line per interpretation of the bytes, missing when too short
+pub fn timestamps(bytes: &[u8], little_endian: bool) -> Vec<(&'static str, Option<String>)> {
+    let mut lines = Vec::new();
+    if let Some(b) = bytes.get(0..4) {
+        let b: [u8; 4] = b.try_into().unwrap();
+        let v = if little_endian {
+            u32::from_le_bytes(b)
+        } else {
+            u32::from_be_bytes(b)
+        };
+        lines.push(("unix32", unix_seconds(v as i64)));
+        lines.push(("dos", dos_datetime(v)));
+    }
+    if let Some(b) = bytes.get(0..8) {
+        let b: [u8; 8] = b.try_into().unwrap();
+        let v = if little_endian {
+            u64::from_le_bytes(b)
+        } else {
+            u64::from_be_bytes(b)
+        };
+        lines.push(("unix64", unix_seconds(v as i64)));
+        lines.push(("unix64 ms", unix_millis(v as i64)));
+        lines.push(("filetime", filetime(v)));
+    }
+    lines
+}
+
+// windows layout: first three fields little endian, the last eight bytes as stored
+pub fn guid(b: &[u8; 16]) -> String {
+    format!(
+        "{{{:08X}-{:04X}-{:04X}-{:02X}{:02X}-{:02X}{:02X}{:02X}{:02X}{:02X}{:02X}}}",
+        u32::from_le_bytes([b[0], b[1], b[2], b[3]]),
+        u16::from_le_bytes([b[4], b[5]]),
+        u16::from_le_bytes([b[6], b[7]]),
+        b[8],
+        b[9],
+        b[10],
+        b[11],
+        b[12],
+        b[13],
+        b[14],
+        b[15]
+    )
+}
+
+// RFC 4122 layout: all fields big endian, i.e. the bytes in stored order
+pub fn uuid(b: &[u8; 16]) -> String {
+    let hex: Vec<String> = b.iter().map(|v| format!("{:02x}", v)).collect();
+    format!(
+        "{}-{}-{}-{}-{}",
+        hex[0..4].concat(),
+        hex[4..6].concat(),
+        hex[6..8].concat(),
+        hex[8..10].concat(),
+        hex[10..16].concat()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // EFI system partition type as stored in a GPT partition entry
+    const ESP: [u8; 16] = [
+        0x28, 0x73, 0x2a, 0xc1, 0x1f, 0xf8, 0xd2, 0x11, 0xba, 0x4b, 0x00, 0xa0, 0xc9, 0x3e, 0xc9,
+        0x3b,
+    ];
+
+    #[test]
+    fn formats_guids() {
+        assert_eq!(guid(&ESP), "{C12A7328-F81F-11D2-BA4B-00A0C93EC93B}");
+        let mut iunknown = [0u8; 16];
+        iunknown[8] = 0xc0;
+        iunknown[15] = 0x46;
+        assert_eq!(guid(&iunknown), "{00000000-0000-0000-C000-000000000046}");
+    }
+
+    #[test]
+    fn formats_uuids() {
+        assert_eq!(uuid(&ESP), "28732ac1-1ff8-d211-ba4b-00a0c93ec93b");
+        let nil = [0u8; 16];
+        assert_eq!(uuid(&nil), "00000000-0000-0000-0000-000000000000");
+    }
+}
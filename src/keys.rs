@@ -0,0 +1,407 @@
+use crossterm::event::KeyCode;
+use crossterm::event::KeyEvent;
+use crossterm::event::KeyModifiers;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Action {
+    Help,
+    Prompt,
+    Quit,
+    Write,
+    DecreaseWidth,
+    IncreaseWidth,
+    NextDisplay,
+    PrevDisplay,
+    NextElement,
+    PrevElement,
+    NextMode,
+    PrevMode,
+    NextHit,
+    PrevHit,
+    NextHitGroup,
+    PrevHitGroup,
+    NextTheme,
+    ToggleHistory,
+    Up,
+    Down,
+    Left,
+    Right,
+    PageUp,
+    PageDown,
+    Home,
+    End,
+    Insert,
+    FollowJump,
+    JumpBack,
+    ToggleAsmBytes,
+    ToggleSqueeze,
+    ToggleSplit,
+    NextSplitDisplay,
+    ToggleHitGroups,
+    RemoveHitGroup,
+    StepForward,
+    StepBackward,
+    ToggleByteClasses,
+    CopyOffset,
+    ToggleRuler,
+    NextPrintGutter,
+    RepeatCommand,
+}
+
+pub const ACTIONS: &[(Action, &str, &str)] = &[
+    (Action::Help, "help", "toggle help"),
+    (Action::Prompt, "prompt", "enter command line"),
+    (Action::Quit, "quit", "exit"),
+    (Action::Write, "write", "save changes to selected file"),
+    (
+        Action::DecreaseWidth,
+        "decrease_width",
+        "decrease print width",
+    ),
+    (
+        Action::IncreaseWidth,
+        "increase_width",
+        "increase print width",
+    ),
+    (Action::NextDisplay, "next_display", "next display mode"),
+    (Action::PrevDisplay, "prev_display", "prev display mode"),
+    (
+        Action::NextElement,
+        "next_element",
+        "next element display mode",
+    ),
+    (
+        Action::PrevElement,
+        "prev_element",
+        "prev element display mode",
+    ),
+    (Action::NextMode, "next_mode", "next interpretation mode"),
+    (Action::PrevMode, "prev_mode", "prev interpretation mode"),
+    (Action::NextHit, "next_hit", "jump to next search hit"),
+    (Action::PrevHit, "prev_hit", "jump to prev search hit"),
+    (
+        Action::NextHitGroup,
+        "next_hit_group",
+        "pick next group of search hits",
+    ),
+    (
+        Action::PrevHitGroup,
+        "prev_hit_group",
+        "pick prev group of search hits",
+    ),
+    (Action::NextTheme, "next_theme", "cycle color themes"),
+    (
+        Action::ToggleHistory,
+        "toggle_history",
+        "toggle history log",
+    ),
+    (Action::Up, "up", "scroll up"),
+    (Action::Down, "down", "scroll down"),
+    (Action::Left, "left", "switch to prev tab"),
+    (Action::Right, "right", "switch to next tab"),
+    (Action::PageUp, "page_up", "scroll page up"),
+    (Action::PageDown, "page_down", "scroll page down"),
+    (
+        Action::Home,
+        "home",
+        "jump to start of file or newest history line",
+    ),
+    (
+        Action::End,
+        "end",
+        "jump to end of file or oldest history line",
+    ),
+    (Action::Insert, "insert", "enter insert mode"),
+    (
+        Action::FollowJump,
+        "follow_jump",
+        "follow branch target in asm view",
+    ),
+    (Action::JumpBack, "jump_back", "return from followed branch"),
+    (
+        Action::ToggleAsmBytes,
+        "toggle_asm_bytes",
+        "toggle instruction bytes in asm view",
+    ),
+    (
+        Action::ToggleSqueeze,
+        "toggle_squeeze",
+        "fold repeated rows in element view",
+    ),
+    (Action::ToggleSplit, "toggle_split", "toggle split view"),
+    (
+        Action::NextSplitDisplay,
+        "next_split_display",
+        "next display mode of split pane",
+    ),
+    (
+        Action::ToggleHitGroups,
+        "toggle_hit_groups",
+        "toggle list of search hit groups",
+    ),
+    (
+        Action::RemoveHitGroup,
+        "remove_hit_group",
+        "remove selected group of search hits",
+    ),
+    (Action::StepForward, "step_forward", "move forward by step"),
+    (
+        Action::StepBackward,
+        "step_backward",
+        "move backward by step",
+    ),
+    (
+        Action::ToggleByteClasses,
+        "toggle_byte_classes",
+        "color zero and printable bytes",
+    ),
+    (
+        Action::CopyOffset,
+        "copy_offset",
+        "copy cursor offset to clipboard",
+    ),
+    (
+        Action::ToggleRuler,
+        "toggle_ruler",
+        "toggle column ruler in element view",
+    ),
+    (
+        Action::NextPrintGutter,
+        "next_print_gutter",
+        "cycle print view gutter: off, offset, line number",
+    ),
+    (
+        Action::RepeatCommand,
+        "repeat_command",
+        "run the last command again",
+    ),
+];
+
+const DEFAULT_BINDINGS: &[(Action, &str)] = &[
+    (Action::Help, "f1"),
+    (Action::Prompt, ":"),
+    (Action::Quit, "Q"),
+    (Action::Write, "W"),
+    (Action::DecreaseWidth, "["),
+    (Action::IncreaseWidth, "]"),
+    (Action::NextDisplay, "p"),
+    (Action::PrevDisplay, "P"),
+    (Action::NextElement, "o"),
+    (Action::PrevElement, "O"),
+    (Action::NextMode, "i"),
+    (Action::PrevMode, "I"),
+    (Action::NextHit, "n"),
+    (Action::PrevHit, "N"),
+    (Action::NextHitGroup, "ctrl+n"),
+    (Action::PrevHitGroup, "ctrl+N"),
+    (Action::NextTheme, "t"),
+    (Action::ToggleHistory, "tab"),
+    (Action::Up, "up"),
+    (Action::Up, "k"),
+    (Action::Down, "down"),
+    (Action::Down, "j"),
+    (Action::Left, "left"),
+    (Action::Left, "h"),
+    (Action::Right, "right"),
+    (Action::Right, "l"),
+    (Action::PageUp, "pageup"),
+    (Action::PageUp, "ctrl+b"),
+    (Action::PageDown, "pagedown"),
+    (Action::PageDown, "ctrl+f"),
+    (Action::Home, "home"),
+    (Action::End, "end"),
+    (Action::Insert, "insert"),
+    (Action::FollowJump, "enter"),
+    (Action::JumpBack, "backspace"),
+    (Action::ToggleAsmBytes, "b"),
+    (Action::ToggleSqueeze, "z"),
+    (Action::ToggleSplit, "s"),
+    (Action::NextSplitDisplay, "S"),
+    (Action::ToggleHitGroups, "g"),
+    (Action::RemoveHitGroup, "X"),
+    (Action::StepForward, ">"),
+    (Action::StepBackward, "<"),
+    (Action::ToggleByteClasses, "c"),
+    (Action::CopyOffset, "y"),
+    (Action::ToggleRuler, "r"),
+    (Action::NextPrintGutter, "#"),
+    (Action::RepeatCommand, "."),
+];
+
+pub struct KeyBindings {
+    pub map: HashMap<(KeyCode, KeyModifiers), Action>,
+}
+
+impl KeyBindings {
+    pub fn default() -> KeyBindings {
+        let mut map = HashMap::new();
+        for (action, key) in DEFAULT_BINDINGS {
+            map.insert(parse_key(key).unwrap(), *action);
+        }
+        KeyBindings { map }
+    }
+
+    pub fn from_file(path: &str) -> Result<(KeyBindings, Vec<String>), Box<dyn Error>> {
+        let mut bindings = KeyBindings::default();
+        let mut conflicts = Vec::new();
+        let mut configured: HashMap<(KeyCode, KeyModifiers), Action> = HashMap::new();
+        let value = fs::read_to_string(path)?.parse::<toml::Value>()?;
+        let table = value.as_table().ok_or("key bindings must be a table")?;
+        for (name, entry) in table {
+            let action = action_by_name(name).ok_or(format!("unknown action: {}", name))?;
+            let mut keys = Vec::new();
+            if let Some(key) = entry.as_str() {
+                keys.push(key);
+            } else if let Some(array) = entry.as_array() {
+                for key in array {
+                    keys.push(
+                        key.as_str()
+                            .ok_or(format!("keys of {} must be strings", name))?,
+                    );
+                }
+            } else {
+                return Err(format!("keys of {} must be a string or an array", name).into());
+            }
+            bindings.map.retain(|_, a| *a != action);
+            for key in keys {
+                let k = parse_key(key).ok_or(format!("unknown key {} for {}", key, name))?;
+                if let Some(other) = configured.get(&k) {
+                    conflicts.push(format!(
+                        "key {} bound to both {} and {}",
+                        key,
+                        action_name(*other),
+                        name
+                    ));
+                } else if let Some(other) = bindings.map.get(&k) {
+                    conflicts.push(format!(
+                        "key {} of {} overrides {}",
+                        key,
+                        name,
+                        action_name(*other)
+                    ));
+                }
+                configured.insert(k, action);
+                bindings.map.insert(k, action);
+            }
+        }
+        Ok((bindings, conflicts))
+    }
+
+    pub fn get(&self, key: &KeyEvent) -> Option<Action> {
+        self.map.get(&normalize(key.code, key.modifiers)).copied()
+    }
+
+    pub fn keys_of(&self, action: Action) -> Vec<String> {
+        let mut keys: Vec<String> = self
+            .map
+            .iter()
+            .filter(|(_, a)| **a == action)
+            .map(|(k, _)| key_name(*k))
+            .collect();
+        keys.sort();
+        keys
+    }
+}
+
+pub fn action_name(action: Action) -> &'static str {
+    ACTIONS.iter().find(|(a, _, _)| *a == action).unwrap().1
+}
+
+fn action_by_name(name: &str) -> Option<Action> {
+    ACTIONS
+        .iter()
+        .find(|(_, n, _)| n.eq(&name))
+        .map(|(a, _, _)| *a)
+}
+
+fn normalize(code: KeyCode, modifiers: KeyModifiers) -> (KeyCode, KeyModifiers) {
+    match code {
+        KeyCode::Char(_) => (code, modifiers - KeyModifiers::SHIFT),
+        _ => (code, modifiers),
+    }
+}
+
+fn parse_key(key: &str) -> Option<(KeyCode, KeyModifiers)> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut name = key;
+    loop {
+        let lower = name.to_lowercase();
+        if name.len() > 1 && lower.starts_with("ctrl+") {
+            modifiers |= KeyModifiers::CONTROL;
+            name = &name[5..];
+        } else if name.len() > 1 && lower.starts_with("alt+") {
+            modifiers |= KeyModifiers::ALT;
+            name = &name[4..];
+        } else if name.len() > 1 && lower.starts_with("shift+") {
+            modifiers |= KeyModifiers::SHIFT;
+            name = &name[6..];
+        } else {
+            break;
+        }
+    }
+    let mut chars = name.chars();
+    let first = chars.next()?;
+    let code = if chars.next().is_none() {
+        KeyCode::Char(first)
+    } else {
+        match name.to_lowercase().as_str() {
+            "tab" => KeyCode::Tab,
+            "backtab" => KeyCode::BackTab,
+            "enter" => KeyCode::Enter,
+            "esc" => KeyCode::Esc,
+            "backspace" => KeyCode::Backspace,
+            "delete" => KeyCode::Delete,
+            "insert" => KeyCode::Insert,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "space" => KeyCode::Char(' '),
+            f => KeyCode::F(f.strip_prefix('f')?.parse::<u8>().ok()?),
+        }
+    };
+    Some(normalize(code, modifiers))
+}
+
+fn key_name(key: (KeyCode, KeyModifiers)) -> String {
+    let (code, modifiers) = key;
+    let mut name = String::new();
+    if modifiers.contains(KeyModifiers::CONTROL) {
+        name.push_str("Ctrl+");
+    }
+    if modifiers.contains(KeyModifiers::ALT) {
+        name.push_str("Alt+");
+    }
+    if modifiers.contains(KeyModifiers::SHIFT) {
+        name.push_str("Shift+");
+    }
+    match code {
+        KeyCode::Char(' ') => name.push_str("space"),
+        KeyCode::Char(c) => name.push(c),
+        KeyCode::F(n) => name.push_str(&format!("f{}", n)),
+        KeyCode::Tab => name.push_str("tab"),
+        KeyCode::BackTab => name.push_str("backtab"),
+        KeyCode::Enter => name.push_str("enter"),
+        KeyCode::Esc => name.push_str("esc"),
+        KeyCode::Backspace => name.push_str("backspace"),
+        KeyCode::Delete => name.push_str("delete"),
+        KeyCode::Insert => name.push_str("insert"),
+        KeyCode::Home => name.push_str("home"),
+        KeyCode::End => name.push_str("end"),
+        KeyCode::PageUp => name.push_str("pageup"),
+        KeyCode::PageDown => name.push_str("pagedown"),
+        KeyCode::Up => name.push_str("up"),
+        KeyCode::Down => name.push_str("down"),
+        KeyCode::Left => name.push_str("left"),
+        KeyCode::Right => name.push_str("right"),
+        _ => name.push('?'),
+    }
+    name
+}
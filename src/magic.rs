@@ -0,0 +1,47 @@
+use std::io;
+use std::io::prelude::*;
+
+const MAGIC: &[(u64, &[u8], &str)] = &[
+    (0, b"\x7fELF", "ELF"),
+    (0, b"MZ", "PE"),
+    (0, b"\xfe\xed\xfa\xce", "Mach-O"),
+    (0, b"\xfe\xed\xfa\xcf", "Mach-O"),
+    (0, b"\xce\xfa\xed\xfe", "Mach-O"),
+    (0, b"\xcf\xfa\xed\xfe", "Mach-O"),
+    (0, b"\xca\xfe\xba\xbe", "Mach-O fat/Java class"),
+    (0, b"PK\x03\x04", "ZIP"),
+    (0, b"\x89PNG\r\n\x1a\n", "PNG"),
+    (0, b"\x1f\x8b", "gzip"),
+    (0, b"BZh", "bzip2"),
+    (0, b"\xfd7zXZ\x00", "xz"),
+    (0, b"7z\xbc\xaf\x27\x1c", "7z"),
+    (0, b"\x28\xb5\x2f\xfd", "zstd"),
+    (0, b"\xff\xd8\xff", "JPEG"),
+    (0, b"GIF87a", "GIF"),
+    (0, b"GIF89a", "GIF"),
+    (0, b"%PDF-", "PDF"),
+    (0, b"RIFF", "RIFF"),
+    (0, b"\x00asm", "WebAssembly"),
+    (0, b"SQLite format 3\x00", "SQLite"),
+    (257, b"ustar", "tar"),
+];
+
+const SNIFF_SIZE: u64 = 512;
+
+pub fn detect(bytes: &[u8]) -> Option<&'static str> {
+    for (offset, magic, name) in MAGIC {
+        let start = *offset as usize;
+        let end = start + magic.len();
+        if bytes.len() >= end && bytes[start..end].eq(*magic) {
+            return Some(name);
+        }
+    }
+    None
+}
+
+pub fn sniff(path: &str) -> io::Result<Option<&'static str>> {
+    let file = std::fs::File::open(path)?;
+    let mut bytes = Vec::new();
+    file.take(SNIFF_SIZE).read_to_end(&mut bytes)?;
+    Ok(detect(&bytes))
+}
@@ -1,42 +1,251 @@
 mod app;
 mod block;
+mod clipboard;
+mod config;
 mod crossterm;
 mod data;
+mod decode;
+mod disasm;
+mod dump;
+mod elf;
 mod files;
 mod history;
 mod hits;
+mod inspect;
+mod keys;
+mod magic;
 mod modes;
+mod pe;
 mod print;
+mod recent;
+#[cfg(test)]
+mod scratch;
+mod session;
 mod tabs;
 mod theme;
 mod ui;
 mod undo;
 
+use crate::app::App;
+use crate::app::MAX_TAB_WIDTH;
+use crate::config::config_path;
 use crate::crossterm::run;
-use clap::{arg, command, Command};
+use crate::files::MAX_BLOCK_SIZE;
+use crate::keys::KeyBindings;
+use crate::print::Print;
+use crate::theme::{detect_color_mode, parse_color_mode, Theme};
+use clap::{arg, command, ArgMatches, Command};
 use std::{error::Error, time::Duration};
 
-const ADD_FILE: &str = "add file to edit.";
+const ADD_FILE: &str = "add files to edit.";
+const THEME_FILE: &str = "load theme from TOML file.";
+const KEYS_FILE: &str = "load key bindings from TOML file.";
+const BLOCK_SIZE: &str = "initial block size of opened files.";
+const OFFSET: &str = "initial offset of opened files.";
+const AT: &str = "open ELF and PE files at a section or symbol, e.g. .text or main.";
+const COLOR_MODE: &str = "color support of terminal: truecolor, 256, 16 or mono.";
+const RECENT: &str = "list recently opened files and exit.";
+const HISTORY_LINES: &str = "maximum number of lines kept in history log.";
+const TICK_RATE: &str = "milliseconds between periodic updates such as watched file checks.";
+const NO_MOUSE: &str = "leave the mouse to the terminal so text can be selected.";
+const LOG_FILE: &str = "append history log to file as it is written.";
+const TAB_WIDTH: &str = "columns between tab stops in the ascii print view.";
+const UNDO_LIMIT: &str = "bytes of undo history kept per file, 0 keeps all.";
+const VERIFY_WRITES: &str = "re-read written bytes and report mismatches.";
+const DUMP: &str = "print a range of a file to stdout and exit.";
+const DUMP_LENGTH: &str = "number of bytes to dump, 256 by default.";
+const DUMP_KIND: &str = "byte, word, dword, qword, oword or asm.";
+const DUMP_MODE: &str = "hex, dec, oct or bin value of elements.";
+const DUMP_ARCH: &str = "x86, x86_64, arm or arm64 for the asm kind.";
+const DUMP_JSON: &str = "emit a JSON array instead of text.";
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let mut paths = Vec::new();
-    let tick_rate = Duration::from_millis(1000);
-    let matches = command!()
+fn cli() -> Command<'static> {
+    command!()
         .propagate_version(true)
         .subcommand_required(false)
         .arg_required_else_help(false)
-        .subcommand(Command::new("add").about(ADD_FILE).arg(arg!([NAME])))
-        .get_matches();
-
-    match matches.subcommand() {
-        Some(("add", sub_matches)) => {
-            let r = sub_matches.get_one::<String>("NAME");
-            if r.is_some() {
-                paths.push(r.unwrap().clone());
-            }
+        .arg(arg!(--theme <PATH>).help(THEME_FILE).required(false))
+        .arg(arg!(--color <MODE>).help(COLOR_MODE).required(false))
+        .arg(arg!(--keys <PATH>).help(KEYS_FILE).required(false))
+        .arg(arg!(--"block-size" <SIZE>).help(BLOCK_SIZE).required(false))
+        .arg(arg!(--offset <OFFSET>).help(OFFSET).required(false))
+        .arg(arg!(--at <NAME>).help(AT).required(false))
+        .arg(arg!(--log <PATH>).help(LOG_FILE).required(false))
+        .arg(
+            arg!(--"history-lines" <LINES>)
+                .help(HISTORY_LINES)
+                .required(false),
+        )
+        .arg(
+            arg!(--"tab-width" <COLUMNS>)
+                .help(TAB_WIDTH)
+                .required(false),
+        )
+        .arg(
+            arg!(--"undo-limit" <BYTES>)
+                .help(UNDO_LIMIT)
+                .required(false),
+        )
+        .arg(arg!(--"tick-rate" <MS>).help(TICK_RATE).required(false))
+        .arg(arg!(--"no-mouse").help(NO_MOUSE))
+        .arg(arg!(--recent).help(RECENT))
+        .arg(arg!(--"verify-writes").help(VERIFY_WRITES))
+        .arg(arg!([NAME]...).help(ADD_FILE))
+        .subcommand(
+            Command::new("add")
+                .about(ADD_FILE)
+                .arg(arg!([NAME]...))
+                .arg(arg!(--at <NAME>).help(AT).required(false)),
+        )
+        .subcommand(
+            Command::new("dump")
+                .about(DUMP)
+                .arg(arg!(<NAME>))
+                .arg(arg!(--offset <OFFSET>).help(OFFSET).required(false))
+                .arg(arg!(--length <LENGTH>).help(DUMP_LENGTH).required(false))
+                .arg(arg!(--kind <KIND>).help(DUMP_KIND).required(false))
+                .arg(arg!(--mode <MODE>).help(DUMP_MODE).required(false))
+                .arg(arg!(--arch <ARCH>).help(DUMP_ARCH).required(false))
+                .arg(arg!(--json).help(DUMP_JSON)),
+        )
+}
+
+// paths given bare or after add, and the --at of either
+fn file_args(matches: &ArgMatches) -> (Vec<String>, Option<String>) {
+    let mut paths = Vec::new();
+    if let Some(names) = matches.get_many::<String>("NAME") {
+        paths.extend(names.cloned());
+    }
+    let mut at = matches.get_one::<String>("at").cloned();
+    if let Some(("add", sub_matches)) = matches.subcommand() {
+        if let Some(names) = sub_matches.get_many::<String>("NAME") {
+            paths.extend(names.cloned());
+        }
+        at = sub_matches.get_one::<String>("at").cloned().or(at);
+    }
+    (paths, at)
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut tick_rate = Duration::from_millis(1000);
+    let matches = cli().get_matches();
+
+    if let Some(("dump", sub_matches)) = matches.subcommand() {
+        return dump::run(sub_matches);
+    }
+    if matches.contains_id("recent") {
+        for (i, path) in recent::load().iter().enumerate() {
+            println!("{:>4} {}", i + 1, path);
+        }
+        return Ok(());
+    }
+    if let Some(rate) = matches.get_one::<String>("tick-rate") {
+        let rate = App::parse_u64_number(rate)
+            .map_err(|e| format!("invalid tick rate {}: {}", rate, e))?;
+        if rate == 0 {
+            return Err("tick rate must be above 0".into());
         }
-        _ => {}
+        tick_rate = Duration::from_millis(rate);
+    }
+    let (paths, at) = file_args(&matches);
+
+    let mut theme = Theme::default();
+    if let Some(path) = matches.get_one::<String>("theme") {
+        theme = Theme::from_file(path)?;
+    }
+    let mut color_mode = detect_color_mode();
+    if let Some(mode) = matches.get_one::<String>("color") {
+        color_mode = parse_color_mode(mode).ok_or(format!("unknown color mode: {}", mode))?;
+    }
+
+    let mut app = App::new("rsreit", paths);
+    let mut print = Print::default();
+    if let Some(lines) = matches.get_one::<String>("history-lines") {
+        let lines = App::parse_u64_number(lines)
+            .map_err(|e| format!("invalid history lines {}: {}", lines, e))?;
+        print.history.set_max_lines(lines as usize);
     }
-    run(tick_rate, paths)?;
+    if let Some(path) = matches.get_one::<String>("log") {
+        print
+            .history
+            .set_log(path.clone())
+            .map_err(|e| format!("cannot open log {}: {}", path, e))?;
+    }
+    if let Some(size) = matches.get_one::<String>("block-size") {
+        let size = App::parse_u64_number(size)
+            .map_err(|e| format!("invalid block size {}: {}", size, e))?;
+        if size == 0 || size > MAX_BLOCK_SIZE {
+            return Err(format!("block size must be between 1 and {}", MAX_BLOCK_SIZE).into());
+        }
+        app.files.block_size = size;
+    }
+    if let Some(offset) = matches.get_one::<String>("offset") {
+        app.files.offset = App::parse_u64_number(offset)
+            .map_err(|e| format!("invalid offset {}: {}", offset, e))?;
+    }
+    app.at = at;
+    if let Some(width) = matches.get_one::<String>("tab-width") {
+        let width = App::parse_u64_number(width)
+            .map_err(|e| format!("invalid tab width {}: {}", width, e))?;
+        if width == 0 || width > MAX_TAB_WIDTH {
+            return Err(format!("tab width must be between 1 and {}", MAX_TAB_WIDTH).into());
+        }
+        app.tab_width = width as usize;
+    }
+    if let Some(limit) = matches.get_one::<String>("undo-limit") {
+        let limit = App::parse_u64_number(limit)
+            .map_err(|e| format!("invalid undo limit {}: {}", limit, e))?;
+        app.files.set_undo_limit(limit as usize);
+    }
+    app.verify_writes = matches.contains_id("verify-writes");
+    app.theme = theme;
+    app.color_mode = color_mode;
+    let mut keys_path = config_path("keys.toml").filter(|p| p.exists());
+    if let Some(path) = matches.get_one::<String>("keys") {
+        keys_path = Some(path.into());
+    }
+    if let Some(path) = keys_path {
+        let (keys, conflicts) = KeyBindings::from_file(&path.to_string_lossy())?;
+        app.keys = keys;
+        for conflict in conflicts {
+            print.history.print(app.theme.error, conflict);
+        }
+    }
+    run(tick_rate, !matches.contains_id("no-mouse"), app, print)?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scratch::Scratch;
+
+    fn parse(args: &[&str]) -> (Vec<String>, Option<String>) {
+        file_args(&cli().try_get_matches_from(args).unwrap())
+    }
+
+    #[test]
+    fn takes_several_paths() {
+        assert_eq!(parse(&["rsreit", "a", "b"]).0, ["a", "b"]);
+        let (paths, at) = parse(&["rsreit", "add", "a", "b", "c", "--at", "main"]);
+        assert_eq!(paths, ["a", "b", "c"]);
+        assert_eq!(at.as_deref(), Some("main"));
+        assert!(parse(&["rsreit"]).0.is_empty());
+    }
+
+    #[test]
+    fn opens_a_tab_per_path() {
+        let (file_a, file_b) = (Scratch::with("tab-a", b"a"), Scratch::with("tab-b", b"b"));
+        let (a, b) = (file_a.path(), file_b.path());
+        let missing = Scratch::new("tab-missing").path();
+        let (paths, _) = parse(&["rsreit", "add", &a, &missing, &b]);
+        let mut app = App::new("rsreit", paths);
+        let mut print = Print::default();
+        for path in std::mem::take(&mut app.paths) {
+            app.add_file(&mut print, path);
+        }
+        assert_eq!(app.tabs.tabs.len(), 2);
+        assert_eq!(app.files.files[1].path, b);
+        assert_eq!(app.tabs.tabs[1].fileitem_index, 1);
+    }
+}
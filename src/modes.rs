@@ -1,4 +1,6 @@
-#[derive(Copy, Clone, Eq, PartialEq)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub enum Display {
     Element,
     Asm,
@@ -6,15 +8,16 @@ pub enum Display {
     Visual,
 }
 
-#[derive(Copy, Clone, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub enum ElementDisplay {
     Byte,
     Word,
     DWord,
     QWord,
+    OWord,
 }
 
-#[derive(Copy, Clone, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub enum PrintDisplay {
     ASCIIPrint,
     ASCIIEscape,
@@ -22,21 +25,37 @@ pub enum PrintDisplay {
     UnicodeEscape,
 }
 
-#[derive(Copy, Clone, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub enum VisualDisplay {
     Color,
     Entropy,
 }
 
-#[derive(Copy, Clone, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum ColorMode {
+    TrueColor,
+    Ansi256,
+    Ansi16,
+    Mono,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub enum ElementMode {
     Hex,
     Dec,
     Oct,
     Bin,
+    Bit,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum PrintGutter {
+    Off,
+    Offset,
+    Line,
 }
 
-#[derive(Copy, Clone, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub enum AsmDisplay {
     Nasm,
     Masm,
@@ -44,29 +63,72 @@ pub enum AsmDisplay {
     Intel,
 }
 
-pub fn element_display_size(display: ElementDisplay) -> u16 {
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum AsmArch {
+    X86,
+    X86_64,
+    Arm,
+    Arm64,
+}
+
+pub const fn element_display_size(display: ElementDisplay) -> u16 {
     match display {
         ElementDisplay::Byte => 1,
         ElementDisplay::Word => 2,
         ElementDisplay::DWord => 4,
         ElementDisplay::QWord => 8,
+        ElementDisplay::OWord => 16,
     }
 }
 
-pub fn element_mode_size(mode: ElementMode) -> u16 {
-    match mode {
-        ElementMode::Hex => 2,
-        ElementMode::Dec => 3,
-        ElementMode::Oct => 3,
-        ElementMode::Bin => 8,
+// digits needed for the largest value of the element in the mode's base
+pub const fn element_input_width(display: ElementDisplay, mode: ElementMode) -> u16 {
+    let base = element_mode_base(mode) as u128;
+    let mut max = u128::MAX >> (128 - 8 * element_display_size(display) as u32);
+    let mut width = 1;
+    while max >= base {
+        max /= base;
+        width += 1;
     }
+    width
 }
 
-pub fn element_mode_base(mode: ElementMode) -> u32 {
+pub const fn element_mode_base(mode: ElementMode) -> u32 {
     match mode {
         ElementMode::Hex => 16,
         ElementMode::Dec => 10,
         ElementMode::Oct => 8,
         ElementMode::Bin => 2,
+        ElementMode::Bit => 2,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DISPLAYS: [ElementDisplay; 5] = [
+        ElementDisplay::Byte,
+        ElementDisplay::Word,
+        ElementDisplay::DWord,
+        ElementDisplay::QWord,
+        ElementDisplay::OWord,
+    ];
+
+    fn widths(mode: ElementMode) -> Vec<u16> {
+        DISPLAYS
+            .iter()
+            .map(|display| element_input_width(*display, mode))
+            .collect()
+    }
+
+    #[test]
+    fn input_widths_fit_the_largest_value() {
+        assert_eq!(widths(ElementMode::Dec), [3, 5, 10, 20, 39]);
+        assert_eq!(widths(ElementMode::Hex), [2, 4, 8, 16, 32]);
+        assert_eq!(widths(ElementMode::Oct), [3, 6, 11, 22, 43]);
+        assert_eq!(widths(ElementMode::Bin), [8, 16, 32, 64, 128]);
+        assert_eq!(u128::MAX.to_string().len(), 39);
+        assert_eq!(format!("{:o}", u64::MAX).len(), 22);
     }
 }
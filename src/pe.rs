@@ -0,0 +1,167 @@
+use std::io;
+use std::os::unix::prelude::FileExt;
+
+const MAX_SECTIONS: u64 = 96;
+
+pub struct Section {
+    pub name: String,
+    pub virtual_size: u64,
+    pub virtual_address: u64,
+    pub raw_size: u64,
+    pub raw_offset: u64,
+}
+
+pub struct Pe {
+    pub pe32_plus: bool,
+    pub machine: u16,
+    pub entry: u64,
+    pub image_base: u64,
+    pub sections: Vec<Section>,
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+fn uint(bytes: &[u8], at: usize, size: usize) -> u64 {
+    let mut value = 0u64;
+    for i in 0..size {
+        value = (value << 8) | bytes[at + size - 1 - i] as u64;
+    }
+    value
+}
+
+fn read(file: &std::fs::File, offset: u64, len: usize) -> io::Result<Vec<u8>> {
+    let mut buf = vec![0u8; len];
+    file.read_exact_at(&mut buf, offset)?;
+    Ok(buf)
+}
+
+impl Pe {
+    pub fn parse(path: &str) -> io::Result<Pe> {
+        let file = std::fs::File::open(path)?;
+        let dos = read(&file, 0, 64).map_err(|_| invalid("file too short for DOS header"))?;
+        if !dos[0..2].eq(b"MZ") {
+            return Err(invalid("not a PE file"));
+        }
+        let lfanew = uint(&dos, 0x3c, 4);
+        let nt =
+            read(&file, lfanew, 24).map_err(|_| invalid("e_lfanew points past end of file"))?;
+        if !nt[0..4].eq(b"PE\0\0") {
+            return Err(invalid("missing PE signature"));
+        }
+        let machine = uint(&nt, 4, 2) as u16;
+        let nsections = uint(&nt, 6, 2);
+        let optsize = uint(&nt, 20, 2);
+        if nsections > MAX_SECTIONS {
+            return Err(invalid("too many PE sections"));
+        }
+        let optoff = lfanew + 24;
+        let opt = read(&file, optoff, std::cmp::max(optsize, 32) as usize)?;
+        let pe32_plus = match uint(&opt, 0, 2) {
+            0x10b => false,
+            0x20b => true,
+            _ => return Err(invalid("unknown optional header magic")),
+        };
+        let entry = uint(&opt, 16, 4);
+        let image_base = if pe32_plus {
+            uint(&opt, 24, 8)
+        } else {
+            uint(&opt, 28, 4)
+        };
+
+        let mut sections = Vec::new();
+        let table = read(&file, optoff + optsize, (nsections * 40) as usize)?;
+        for sh in table.chunks(40) {
+            let end = sh[0..8].iter().position(|&b| b == 0).unwrap_or(8);
+            sections.push(Section {
+                name: String::from_utf8_lossy(&sh[0..end]).to_string(),
+                virtual_size: uint(sh, 8, 4),
+                virtual_address: uint(sh, 12, 4),
+                raw_size: uint(sh, 16, 4),
+                raw_offset: uint(sh, 20, 4),
+            });
+        }
+
+        Ok(Pe {
+            pe32_plus,
+            machine,
+            entry,
+            image_base,
+            sections,
+        })
+    }
+
+    pub fn rva_to_offset(&self, rva: u64) -> Option<u64> {
+        for section in &self.sections {
+            let size = std::cmp::max(section.virtual_size, section.raw_size);
+            if rva >= section.virtual_address && rva - section.virtual_address < size {
+                let delta = rva - section.virtual_address;
+                if delta < section.raw_size {
+                    return Some(section.raw_offset + delta);
+                }
+                return None;
+            }
+        }
+        None
+    }
+
+    pub fn resolve(&self, name: &str) -> Option<u64> {
+        self.sections
+            .iter()
+            .find(|s| s.name.eq(name) && s.raw_size > 0)
+            .map(|s| s.raw_offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scratch::Scratch;
+
+    const OPT: usize = 0x58;
+    const TABLE: usize = OPT + 0xf0;
+
+    fn put(image: &mut [u8], at: usize, value: u64, size: usize) {
+        image[at..at + size].copy_from_slice(&value.to_le_bytes()[..size]);
+    }
+
+    // PE32+ image with a .text section and an uninitialized .bss
+    fn image() -> Vec<u8> {
+        let mut image = vec![0u8; 0x400];
+        image[..2].copy_from_slice(b"MZ");
+        put(&mut image, 0x3c, 0x40, 4);
+        image[0x40..0x44].copy_from_slice(b"PE\0\0");
+        put(&mut image, 0x44, 0x8664, 2);
+        put(&mut image, 0x46, 2, 2);
+        put(&mut image, 0x54, 0xf0, 2);
+        put(&mut image, OPT, 0x20b, 2);
+        put(&mut image, OPT + 16, 0x1010, 4);
+        put(&mut image, OPT + 24, 0x140000000, 8);
+        for (i, (name, raw_size)) in [(&b".text"[..], 0x200), (&b".bss"[..], 0)]
+            .iter()
+            .enumerate()
+        {
+            let sh = TABLE + i * 40;
+            image[sh..sh + name.len()].copy_from_slice(name);
+            put(&mut image, sh + 8, 0x200, 4);
+            put(&mut image, sh + 12, 0x1000 * (i as u64 + 1), 4);
+            put(&mut image, sh + 16, *raw_size, 4);
+            put(&mut image, sh + 20, 0x200, 4);
+        }
+        image
+    }
+
+    #[test]
+    fn resolves_sections() {
+        let file = Scratch::with("pe", image());
+        let pe = Pe::parse(&file.path()).unwrap();
+        assert!(pe.pe32_plus);
+        assert_eq!(pe.image_base, 0x140000000);
+        assert_eq!(pe.rva_to_offset(pe.entry), Some(0x210));
+        assert_eq!(pe.resolve(".text"), Some(0x200));
+        // no raw data to open at, and PE files carry no symbol table to fall back to
+        assert_eq!(pe.resolve(".bss"), None);
+        assert_eq!(pe.resolve("main"), None);
+    }
+}
@@ -59,22 +59,22 @@ impl<'a> Print<'a> {
     }
 
     pub fn hexdword(&mut self, app: &mut App<'a>) {
-        let buffer = App::get_hexword(app);
+        let buffer = App::get_hexdword(app);
         self.history.add(buffer);
     }
 
     pub fn decdword(&mut self, app: &mut App<'a>) {
-        let buffer = App::get_decword(app);
+        let buffer = App::get_decdword(app);
         self.history.add(buffer);
     }
 
     pub fn octdword(&mut self, app: &mut App<'a>) {
-        let buffer = App::get_octword(app);
+        let buffer = App::get_octdword(app);
         self.history.add(buffer);
     }
 
     pub fn bindword(&mut self, app: &mut App<'a>) {
-        let buffer = App::get_binword(app);
+        let buffer = App::get_bindword(app);
         self.history.add(buffer);
     }
 
@@ -98,6 +98,26 @@ impl<'a> Print<'a> {
         self.history.add(buffer);
     }
 
+    pub fn hexoword(&mut self, app: &mut App<'a>) {
+        let buffer = App::get_hexoword(app);
+        self.history.add(buffer);
+    }
+
+    pub fn decoword(&mut self, app: &mut App<'a>) {
+        let buffer = App::get_decoword(app);
+        self.history.add(buffer);
+    }
+
+    pub fn octoword(&mut self, app: &mut App<'a>) {
+        let buffer = App::get_octoword(app);
+        self.history.add(buffer);
+    }
+
+    pub fn binoword(&mut self, app: &mut App<'a>) {
+        let buffer = App::get_binoword(app);
+        self.history.add(buffer);
+    }
+
     pub fn asm(&mut self, app: &mut App<'a>) {
         let buffer = App::get_asm(app);
         self.history.add(buffer);
@@ -0,0 +1,34 @@
+use crate::config::config_path;
+use std::fs;
+use std::io;
+
+const RECENT_FILE: &str = "recent";
+const MAX_RECENT: usize = 20;
+
+pub fn load() -> Vec<String> {
+    config_path(RECENT_FILE)
+        .and_then(|file| fs::read_to_string(file).ok())
+        .map(|text| {
+            text.lines()
+                .filter(|line| !line.is_empty())
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+pub fn remember(path: &str) -> io::Result<()> {
+    let file = config_path(RECENT_FILE)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no config directory"))?;
+    let path = fs::canonicalize(path)
+        .map(|p| p.to_string_lossy().into_owned())
+        .unwrap_or_else(|_| path.to_string());
+    let mut recent = load();
+    recent.retain(|p| *p != path);
+    recent.insert(0, path);
+    recent.truncate(MAX_RECENT);
+    if let Some(dir) = file.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(file, recent.join("\n") + "\n")
+}
@@ -0,0 +1,31 @@
+use crate::files::Files;
+use crate::tabs::Tabs;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+
+#[derive(Serialize, Deserialize)]
+pub struct Session {
+    pub files: Files,
+    pub tabs: Tabs,
+}
+
+impl Session {
+    pub fn new(files: &Files, tabs: &Tabs) -> Session {
+        Session {
+            files: files.clone(),
+            tabs: tabs.clone(),
+        }
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json)
+    }
+
+    pub fn load(path: &str) -> io::Result<Session> {
+        let json = fs::read_to_string(path)?;
+        let session: Session = serde_json::from_str(&json)?;
+        Ok(session)
+    }
+}
@@ -1,13 +1,16 @@
 use crate::modes::element_display_size;
-use crate::modes::element_mode_size;
+use crate::modes::element_input_width;
+use crate::modes::AsmArch;
 use crate::modes::AsmDisplay;
 use crate::modes::Display;
 use crate::modes::ElementDisplay;
 use crate::modes::ElementMode;
 use crate::modes::PrintDisplay;
+use crate::modes::PrintGutter;
 use crate::modes::VisualDisplay;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Eq, PartialEq)]
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Tab {
     pub title: String,
     pub fileitem_index: usize,
@@ -18,20 +21,94 @@ pub struct Tab {
     pub print_display: PrintDisplay,
     pub element_mode: ElementMode,
     pub asm_display: AsmDisplay,
+    #[serde(default = "default_asm_arch")]
+    pub asm_arch: AsmArch,
     pub visual_display: VisualDisplay,
+    #[serde(skip)]
     pub insert_mode: bool,
+    #[serde(skip)]
     pub insert_index: usize,
-    pub insert_vector: [u8; 64],
+    #[serde(skip, default = "empty_insert_vector")]
+    pub insert_vector: [u8; INSERT_VECTOR_SIZE],
+    #[serde(skip)]
+    pub insert_error: bool,
     pub cursor_row: u16,
     pub cursor_column: u16,
+    #[serde(default = "default_show_asm_bytes")]
+    pub show_asm_bytes: bool,
+    #[serde(default)]
+    pub squeeze: bool,
+    #[serde(default)]
+    pub split: bool,
+    #[serde(default = "default_split_display")]
+    pub split_display: Display,
+    #[serde(default)]
+    pub wrap_navigation: bool,
+    #[serde(default = "default_snap_elements")]
+    pub snap_elements: bool,
+    #[serde(default)]
+    pub block_size: u64,
+    // view offset of this tab, tabs on the same file share its block
+    #[serde(default)]
+    pub offset: u64,
+    #[serde(default = "default_step")]
+    pub step: u64,
+    #[serde(default)]
+    pub byte_classes: bool,
+    #[serde(default)]
+    pub rows: u16,
+    #[serde(default)]
+    pub ruler: bool,
+    #[serde(default)]
+    pub rel_base: u64,
+    #[serde(default = "default_print_gutter")]
+    pub print_gutter: PrintGutter,
+    // absolute offsets of batch edit cursors, the first one follows the main cursor
+    #[serde(skip)]
+    pub cursors: Vec<u64>,
+    // other end of the selection, the cursor extends it
+    #[serde(skip)]
+    pub anchor: Option<u64>,
 }
 
-#[derive(Clone, Eq, PartialEq)]
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Tabs {
     pub tabs: Vec<Tab>,
     pub index: usize,
 }
 
+// large enough for the binary digits of the widest element
+pub const INSERT_VECTOR_SIZE: usize =
+    element_input_width(ElementDisplay::OWord, ElementMode::Bin) as usize;
+
+fn empty_insert_vector() -> [u8; INSERT_VECTOR_SIZE] {
+    [0u8; INSERT_VECTOR_SIZE]
+}
+
+fn default_split_display() -> Display {
+    Display::Asm
+}
+
+fn default_asm_arch() -> AsmArch {
+    AsmArch::X86_64
+}
+
+fn default_print_gutter() -> PrintGutter {
+    PrintGutter::Off
+}
+
+fn default_show_asm_bytes() -> bool {
+    true
+}
+
+fn default_snap_elements() -> bool {
+    true
+}
+
+fn default_step() -> u64 {
+    16
+}
+
 impl Tabs {
     pub fn default() -> Tabs {
         Tabs {
@@ -56,7 +133,7 @@ impl Tabs {
         }
     }
 
-    pub fn add(&mut self, title: String) {
+    pub fn add(&mut self, title: String, block_size: u64, offset: u64) {
         let new_tab = Tab {
             title,
             fileitem_index: 0,
@@ -67,16 +144,41 @@ impl Tabs {
             print_display: PrintDisplay::ASCIIPrint,
             element_mode: ElementMode::Hex,
             asm_display: AsmDisplay::Nasm,
+            asm_arch: AsmArch::X86_64,
             visual_display: VisualDisplay::Color,
             insert_mode: false,
             insert_index: 0,
-            insert_vector: [0u8; 64],
+            insert_vector: [0u8; INSERT_VECTOR_SIZE],
+            insert_error: false,
             cursor_row: 0,
             cursor_column: 0,
+            show_asm_bytes: true,
+            squeeze: false,
+            split: false,
+            split_display: Display::Asm,
+            wrap_navigation: false,
+            snap_elements: true,
+            block_size,
+            offset,
+            step: 16,
+            byte_classes: false,
+            rows: 0,
+            ruler: false,
+            rel_base: 0,
+            print_gutter: PrintGutter::Off,
+            cursors: Vec::new(),
+            anchor: None,
         };
         self.tabs.push(new_tab);
     }
 
+    pub fn clone_current(&mut self) {
+        let mut tab = self.tabs[self.index].clone();
+        tab.title = format!("tab{}", self.tabs.len());
+        self.tabs.push(tab);
+        self.index = self.tabs.len() - 1;
+    }
+
     pub fn current(&mut self) -> &mut Tab {
         let tab_index = self.index;
         &mut self.tabs[tab_index]
@@ -96,7 +198,7 @@ impl Tabs {
     }
 
     pub fn element_input_size(tab: &mut Tab) -> u16 {
-        element_display_size(tab.element_display) * element_mode_size(tab.element_mode)
+        element_input_width(tab.element_display, tab.element_mode)
     }
 
     pub fn element_mode(&mut self, mode: String) {
@@ -108,6 +210,8 @@ impl Tabs {
             self.current().element_mode = ElementMode::Oct;
         } else if mode.eq("bin") {
             self.current().element_mode = ElementMode::Bin;
+        } else if mode.eq("bit") {
+            self.current().element_mode = ElementMode::Bit;
         }
     }
 
@@ -144,3 +248,18 @@ impl Tabs {
         self.current().cursor_column = column & !(size - 1) as u16;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tabs_from_older_sessions_load() {
+        let mut tabs = Tabs::default();
+        tabs.add("tab0".to_string(), 2048, 0);
+        let mut json = serde_json::to_value(&tabs.tabs[0]).unwrap();
+        json.as_object_mut().unwrap().remove("show_asm_bytes");
+        let tab: Tab = serde_json::from_value(json).unwrap();
+        assert!(tab.show_asm_bytes);
+    }
+}
@@ -1,3 +1,7 @@
+use crate::modes::ColorMode;
+use std::env;
+use std::error::Error;
+use std::fs;
 use tui::style::Color;
 use tui::style::Modifier;
 use tui::style::Style;
@@ -5,6 +9,7 @@ use tui::style::Style;
 #[derive(Copy, Clone)]
 pub struct Theme {
     pub ascii: Style,
+    pub bookmark: Style,
     pub current_offset: Style,
     pub current_text: Style,
     pub data: Style,
@@ -15,6 +20,8 @@ pub struct Theme {
     pub function: Style,
     pub functionaddress: Style,
     pub header: Style,
+    pub hit: Style,
+    pub invalid: Style,
     pub keyword: Style,
     pub label: Style,
     pub labeladdress: Style,
@@ -26,9 +33,12 @@ pub struct Theme {
     pub prefix: Style,
     pub punctuation: Style,
     pub register: Style,
+    pub ruler: Style,
+    pub selection: Style,
     pub selectorvalue: Style,
     pub tab: Style,
     pub text: Style,
+    pub zero: Style,
 }
 
 impl Theme {
@@ -46,6 +56,8 @@ impl Theme {
                 .fg(Color::Green)
                 .bg(Color::Black)
                 .add_modifier(Modifier::BOLD),
+            hit: Style::default().fg(Color::Black).bg(Color::Cyan),
+            invalid: Style::default().fg(Color::LightRed).bg(Color::Black),
             keyword: Style::default().fg(Color::Cyan).bg(Color::Black),
             label: Style::default()
                 .fg(Color::Rgb(0x11, 0xaa, 0x33))
@@ -64,8 +76,375 @@ impl Theme {
             noascii: Style::default().fg(Color::Red).bg(Color::Black),
             text: Style::default().fg(Color::White).bg(Color::Black),
             null: Style::default().fg(Color::Black).bg(Color::Black),
+            zero: Style::default().fg(Color::DarkGray).bg(Color::Black),
+            ruler: Style::default().fg(Color::DarkGray).bg(Color::Black),
+            selection: Style::default().fg(Color::White).bg(Color::Blue),
             tab: Style::default().fg(Color::Cyan).bg(Color::Black),
             edited: Style::default().fg(Color::Yellow).bg(Color::Rgb(0x20, 0x20, 0x20)),
+            bookmark: Style::default().fg(Color::Black).bg(Color::Magenta),
         }
     }
+
+    pub fn light() -> Theme {
+        Theme {
+            current_offset: Style::default().bg(Color::Blue).fg(Color::White),
+            current_text: Style::default().bg(Color::Black).fg(Color::White),
+            data: Style::default()
+                .fg(Color::Rgb(0x80, 0x50, 0x00))
+                .bg(Color::White),
+            decorator: Style::default()
+                .fg(Color::Rgb(0x80, 0x50, 0x00))
+                .bg(Color::White),
+            directive: Style::default().fg(Color::Red).bg(Color::White),
+            error: Style::default().fg(Color::White).bg(Color::Red),
+            function: Style::default()
+                .fg(Color::Rgb(0x80, 0x50, 0x00))
+                .bg(Color::White),
+            functionaddress: Style::default()
+                .fg(Color::Rgb(0x80, 0x50, 0x00))
+                .bg(Color::White),
+            header: Style::default()
+                .fg(Color::Blue)
+                .bg(Color::White)
+                .add_modifier(Modifier::BOLD),
+            hit: Style::default().fg(Color::Black).bg(Color::LightCyan),
+            invalid: Style::default()
+                .fg(Color::Red)
+                .bg(Color::Rgb(0xff, 0xe0, 0xe0)),
+            keyword: Style::default().fg(Color::Magenta).bg(Color::White),
+            label: Style::default()
+                .fg(Color::Rgb(0x00, 0x66, 0x22))
+                .bg(Color::White),
+            labeladdress: Style::default()
+                .fg(Color::Rgb(0x80, 0x60, 0x00))
+                .bg(Color::White),
+            mnemonic: Style::default().fg(Color::Blue).bg(Color::White),
+            number: Style::default()
+                .fg(Color::Rgb(0x80, 0x50, 0x00))
+                .bg(Color::White),
+            offset: Style::default()
+                .fg(Color::Rgb(0x00, 0x66, 0x22))
+                .bg(Color::White),
+            prefix: Style::default().fg(Color::Magenta).bg(Color::White),
+            punctuation: Style::default().fg(Color::DarkGray).bg(Color::White),
+            register: Style::default()
+                .fg(Color::Rgb(0x00, 0x66, 0x22))
+                .bg(Color::White),
+            selectorvalue: Style::default()
+                .fg(Color::Rgb(0x80, 0x50, 0x00))
+                .bg(Color::White),
+            ascii: Style::default()
+                .fg(Color::Rgb(0x80, 0x50, 0x00))
+                .bg(Color::White),
+            noascii: Style::default().fg(Color::Red).bg(Color::White),
+            text: Style::default().fg(Color::Black).bg(Color::White),
+            null: Style::default().fg(Color::White).bg(Color::White),
+            zero: Style::default().fg(Color::Gray).bg(Color::White),
+            ruler: Style::default().fg(Color::Gray).bg(Color::White),
+            selection: Style::default().fg(Color::Black).bg(Color::LightBlue),
+            tab: Style::default().fg(Color::Blue).bg(Color::White),
+            edited: Style::default()
+                .fg(Color::Red)
+                .bg(Color::Rgb(0xe0, 0xe0, 0xe0)),
+            bookmark: Style::default().fg(Color::White).bg(Color::Magenta),
+        }
+    }
+
+    pub fn high_contrast() -> Theme {
+        Theme {
+            current_offset: Style::default().bg(Color::White).fg(Color::Black),
+            current_text: Style::default().bg(Color::White).fg(Color::Black),
+            data: Style::default().fg(Color::LightYellow).bg(Color::Black),
+            decorator: Style::default().fg(Color::LightYellow).bg(Color::Black),
+            directive: Style::default().fg(Color::LightRed).bg(Color::Black),
+            error: Style::default()
+                .fg(Color::White)
+                .bg(Color::Red)
+                .add_modifier(Modifier::BOLD),
+            function: Style::default().fg(Color::LightYellow).bg(Color::Black),
+            functionaddress: Style::default().fg(Color::LightYellow).bg(Color::Black),
+            header: Style::default()
+                .fg(Color::White)
+                .bg(Color::Black)
+                .add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            hit: Style::default()
+                .fg(Color::Black)
+                .bg(Color::LightCyan)
+                .add_modifier(Modifier::BOLD),
+            invalid: Style::default()
+                .fg(Color::White)
+                .bg(Color::Red)
+                .add_modifier(Modifier::BOLD),
+            keyword: Style::default().fg(Color::LightCyan).bg(Color::Black),
+            label: Style::default().fg(Color::LightGreen).bg(Color::Black),
+            labeladdress: Style::default().fg(Color::LightYellow).bg(Color::Black),
+            mnemonic: Style::default()
+                .fg(Color::LightBlue)
+                .bg(Color::Black)
+                .add_modifier(Modifier::BOLD),
+            number: Style::default().fg(Color::LightYellow).bg(Color::Black),
+            offset: Style::default().fg(Color::LightGreen).bg(Color::Black),
+            prefix: Style::default().fg(Color::LightMagenta).bg(Color::Black),
+            punctuation: Style::default().fg(Color::White).bg(Color::Black),
+            register: Style::default().fg(Color::LightGreen).bg(Color::Black),
+            selectorvalue: Style::default().fg(Color::LightYellow).bg(Color::Black),
+            ascii: Style::default().fg(Color::White).bg(Color::Black),
+            noascii: Style::default().fg(Color::LightRed).bg(Color::Black),
+            text: Style::default().fg(Color::White).bg(Color::Black),
+            null: Style::default().fg(Color::Black).bg(Color::Black),
+            zero: Style::default().fg(Color::DarkGray).bg(Color::Black),
+            ruler: Style::default().fg(Color::Gray).bg(Color::Black),
+            selection: Style::default()
+                .fg(Color::Black)
+                .bg(Color::White)
+                .add_modifier(Modifier::BOLD),
+            tab: Style::default().fg(Color::LightCyan).bg(Color::Black),
+            edited: Style::default()
+                .fg(Color::Black)
+                .bg(Color::LightYellow)
+                .add_modifier(Modifier::BOLD),
+            bookmark: Style::default()
+                .fg(Color::Black)
+                .bg(Color::LightMagenta)
+                .add_modifier(Modifier::BOLD),
+        }
+    }
+
+    pub fn preset(name: &str) -> Option<Theme> {
+        match name {
+            "default" => Some(Theme::default()),
+            "light" => Some(Theme::light()),
+            "high_contrast" => Some(Theme::high_contrast()),
+            _ => None,
+        }
+    }
+
+    pub fn from_file(path: &str) -> Result<Theme, Box<dyn Error>> {
+        let mut theme = Theme::default();
+        let value = fs::read_to_string(path)?.parse::<toml::Value>()?;
+        let table = value.as_table().ok_or("theme must be a table")?;
+        for (name, entry) in table {
+            let style = theme
+                .style_mut(name)
+                .ok_or(format!("unknown style: {}", name))?;
+            let entry = entry
+                .as_table()
+                .ok_or(format!("style {} must be a table", name))?;
+            if let Some(fg) = entry.get("fg") {
+                *style = style.fg(parse_color(fg)?);
+            }
+            if let Some(bg) = entry.get("bg") {
+                *style = style.bg(parse_color(bg)?);
+            }
+            if let Some(modifiers) = entry.get("modifiers") {
+                let modifiers = modifiers
+                    .as_array()
+                    .ok_or(format!("modifiers of {} must be an array", name))?;
+                for m in modifiers {
+                    *style = style.add_modifier(parse_modifier(m)?);
+                }
+            }
+        }
+        Ok(theme)
+    }
+
+    fn style_mut(&mut self, name: &str) -> Option<&mut Style> {
+        match name {
+            "ascii" => Some(&mut self.ascii),
+            "bookmark" => Some(&mut self.bookmark),
+            "current_offset" => Some(&mut self.current_offset),
+            "current_text" => Some(&mut self.current_text),
+            "data" => Some(&mut self.data),
+            "decorator" => Some(&mut self.decorator),
+            "directive" => Some(&mut self.directive),
+            "edited" => Some(&mut self.edited),
+            "error" => Some(&mut self.error),
+            "function" => Some(&mut self.function),
+            "functionaddress" => Some(&mut self.functionaddress),
+            "header" => Some(&mut self.header),
+            "hit" => Some(&mut self.hit),
+            "invalid" => Some(&mut self.invalid),
+            "keyword" => Some(&mut self.keyword),
+            "label" => Some(&mut self.label),
+            "labeladdress" => Some(&mut self.labeladdress),
+            "mnemonic" => Some(&mut self.mnemonic),
+            "noascii" => Some(&mut self.noascii),
+            "null" => Some(&mut self.null),
+            "number" => Some(&mut self.number),
+            "offset" => Some(&mut self.offset),
+            "prefix" => Some(&mut self.prefix),
+            "punctuation" => Some(&mut self.punctuation),
+            "register" => Some(&mut self.register),
+            "ruler" => Some(&mut self.ruler),
+            "selection" => Some(&mut self.selection),
+            "selectorvalue" => Some(&mut self.selectorvalue),
+            "tab" => Some(&mut self.tab),
+            "text" => Some(&mut self.text),
+            "zero" => Some(&mut self.zero),
+            _ => None,
+        }
+    }
+}
+
+const ANSI16: &[(Color, u8, u8, u8)] = &[
+    (Color::Black, 0x00, 0x00, 0x00),
+    (Color::Red, 0x80, 0x00, 0x00),
+    (Color::Green, 0x00, 0x80, 0x00),
+    (Color::Yellow, 0x80, 0x80, 0x00),
+    (Color::Blue, 0x00, 0x00, 0x80),
+    (Color::Magenta, 0x80, 0x00, 0x80),
+    (Color::Cyan, 0x00, 0x80, 0x80),
+    (Color::Gray, 0xc0, 0xc0, 0xc0),
+    (Color::DarkGray, 0x80, 0x80, 0x80),
+    (Color::LightRed, 0xff, 0x00, 0x00),
+    (Color::LightGreen, 0x00, 0xff, 0x00),
+    (Color::LightYellow, 0xff, 0xff, 0x00),
+    (Color::LightBlue, 0x00, 0x00, 0xff),
+    (Color::LightMagenta, 0xff, 0x00, 0xff),
+    (Color::LightCyan, 0x00, 0xff, 0xff),
+    (Color::White, 0xff, 0xff, 0xff),
+];
+
+const DENSITY: &[&str] = &[" ", "░", "▒", "▓", "█"];
+
+pub fn detect_color_mode() -> ColorMode {
+    let colorterm = env::var("COLORTERM").unwrap_or_default();
+    let term = env::var("TERM").unwrap_or_default();
+    if colorterm.eq("truecolor") || colorterm.eq("24bit") {
+        ColorMode::TrueColor
+    } else if term.contains("256color") {
+        ColorMode::Ansi256
+    } else {
+        ColorMode::Ansi16
+    }
+}
+
+pub fn parse_color_mode(name: &str) -> Option<ColorMode> {
+    match name {
+        "truecolor" => Some(ColorMode::TrueColor),
+        "256" => Some(ColorMode::Ansi256),
+        "16" => Some(ColorMode::Ansi16),
+        "mono" => Some(ColorMode::Mono),
+        _ => None,
+    }
+}
+
+pub fn rgb_color(mode: ColorMode, red: u8, green: u8, blue: u8) -> Color {
+    match mode {
+        ColorMode::TrueColor => Color::Rgb(red, green, blue),
+        ColorMode::Ansi256 => {
+            let r = (red as u16 * 5 + 127) / 255;
+            let g = (green as u16 * 5 + 127) / 255;
+            let b = (blue as u16 * 5 + 127) / 255;
+            Color::Indexed((16 + 36 * r + 6 * g + b) as u8)
+        }
+        ColorMode::Ansi16 => {
+            let mut best = Color::Black;
+            let mut best_distance = u32::MAX;
+            for (color, r, g, b) in ANSI16 {
+                let dr = (red as i32 - *r as i32).pow(2) as u32;
+                let dg = (green as i32 - *g as i32).pow(2) as u32;
+                let db = (blue as i32 - *b as i32).pow(2) as u32;
+                if dr + dg + db < best_distance {
+                    best_distance = dr + dg + db;
+                    best = *color;
+                }
+            }
+            best
+        }
+        ColorMode::Mono => Color::Reset,
+    }
+}
+
+pub fn density_glyph(level: u8) -> &'static str {
+    DENSITY[(level as usize * (DENSITY.len() - 1) + 127) / 255]
+}
+
+fn parse_color(value: &toml::Value) -> Result<Color, Box<dyn Error>> {
+    let name = value.as_str().ok_or("color must be a string")?;
+    if let Some(hex) = name.strip_prefix('#') {
+        if hex.len() == 6 {
+            let rgb = u32::from_str_radix(hex, 16)?;
+            return Ok(Color::Rgb((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8));
+        }
+    }
+    let color = match name.to_lowercase().as_str() {
+        "reset" => Color::Reset,
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "gray" => Color::Gray,
+        "darkgray" => Color::DarkGray,
+        "lightred" => Color::LightRed,
+        "lightgreen" => Color::LightGreen,
+        "lightyellow" => Color::LightYellow,
+        "lightblue" => Color::LightBlue,
+        "lightmagenta" => Color::LightMagenta,
+        "lightcyan" => Color::LightCyan,
+        "white" => Color::White,
+        _ => return Err(format!("unknown color: {}", name).into()),
+    };
+    Ok(color)
+}
+
+fn parse_modifier(value: &toml::Value) -> Result<Modifier, Box<dyn Error>> {
+    let name = value.as_str().ok_or("modifier must be a string")?;
+    let modifier = match name.to_lowercase().as_str() {
+        "bold" => Modifier::BOLD,
+        "dim" => Modifier::DIM,
+        "italic" => Modifier::ITALIC,
+        "underlined" => Modifier::UNDERLINED,
+        "slow_blink" => Modifier::SLOW_BLINK,
+        "rapid_blink" => Modifier::RAPID_BLINK,
+        "reversed" => Modifier::REVERSED,
+        "hidden" => Modifier::HIDDEN,
+        "crossed_out" => Modifier::CROSSED_OUT,
+        _ => return Err(format!("unknown modifier: {}", name).into()),
+    };
+    Ok(modifier)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scratch::Scratch;
+
+    fn load(name: &str, toml: &str) -> Result<Theme, Box<dyn Error>> {
+        let file = Scratch::with(&format!("{}.toml", name), toml);
+        Theme::from_file(&file.path())
+    }
+
+    #[test]
+    fn loads_styles() {
+        let theme = load(
+            "valid",
+            "[hit]\nfg = \"#102030\"\nbg = \"Blue\"\nmodifiers = [\"bold\", \"underlined\"]\n",
+        )
+        .unwrap();
+        let hit = Style::default()
+            .fg(Color::Rgb(0x10, 0x20, 0x30))
+            .bg(Color::Blue)
+            .add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+        assert_eq!(theme.hit, hit);
+        assert_eq!(theme.text, Theme::default().text);
+    }
+
+    #[test]
+    fn rejects_unknown_style() {
+        let err = load("style", "[hits]\nfg = \"red\"\n").err().unwrap();
+        assert_eq!(err.to_string(), "unknown style: hits");
+    }
+
+    #[test]
+    fn rejects_bad_colors() {
+        let err = load("color", "[hit]\nfg = \"purple\"\n").err().unwrap();
+        assert_eq!(err.to_string(), "unknown color: purple");
+        assert!(load("hex", "[hit]\nfg = \"#12345g\"\n").is_err());
+        assert!(load("type", "[hit]\nbg = 3\n").is_err());
+        assert!(load("modifier", "[hit]\nmodifiers = [\"shiny\"]\n").is_err());
+    }
 }
@@ -1,4 +1,5 @@
 use crate::app::App;
+use crate::modes::Display;
 use crate::print::Print;
 use tui::{
     backend::Backend,
@@ -14,10 +15,12 @@ pub fn draw<B: Backend>(f: &mut Frame<B>, app: &mut App, print: &mut Print) {
         draw_help(f, app);
     } else if app.show_history {
         draw_history(f, app, print);
+    } else if app.show_hits {
+        draw_hit_groups(f, app);
     } else {
         let mut tab_titles = Vec::new();
-        for e in &(app.tabs.tabs) {
-            tab_titles.push(e.title.clone());
+        for i in 0..app.tabs.tabs.len() {
+            tab_titles.push(app.tab_title(i));
         }
 
         let titles = tab_titles
@@ -29,14 +32,39 @@ pub fn draw<B: Backend>(f: &mut Frame<B>, app: &mut App, print: &mut Print) {
             .select(app.tabs.index);
         let rect = Rect::new(0, 0, f.size().width, 1);
         f.render_widget(tabs, rect);
-        let rect = Rect::new(0, 1, f.size().width, f.size().height - 2);
+        let rect = Rect::new(
+            0,
+            1,
+            f.size().width.saturating_sub(1),
+            f.size().height.saturating_sub(3),
+        );
         draw_tabs(f, app, rect);
+        draw_scrollbar(f, app, Rect::new(rect.width, rect.y, 1, rect.height));
+        if let Some(status) = app.status_line() {
+            let rect = Rect::new(0, f.size().height.saturating_sub(2), f.size().width, 1);
+            let paragraph = Paragraph::new(Spans::from(Span::styled(status, app.theme.header)));
+            f.render_widget(paragraph, rect);
+        }
     }
 
     if app.enter_prompt {
         let rect = Rect::new(0, f.size().height - 1, f.size().width, 1);
         let widget = app.textarea.widget();
         f.render_widget(widget, rect);
+    } else if app.show_history && (print.history.searching || !print.history.query.is_empty()) {
+        let rect = Rect::new(0, f.size().height - 1, f.size().width, 1);
+        let mut search = format!("/{}", print.history.query);
+        if !print.history.found {
+            search.push_str(" (no match)");
+        }
+        let paragraph = Paragraph::new(Spans::from(Span::styled(search, app.theme.text)));
+        f.render_widget(paragraph, rect);
+    } else if !app.show_help && !app.show_history && !app.show_hits {
+        if let Some(status) = app.hit_status() {
+            let rect = Rect::new(0, f.size().height - 1, f.size().width, 1);
+            let paragraph = Paragraph::new(Spans::from(Span::styled(status, app.theme.text)));
+            f.render_widget(paragraph, rect);
+        }
     }
 }
 
@@ -53,13 +81,50 @@ fn draw_tab<B>(f: &mut Frame<B>, app: &mut App, area: Rect)
 where
     B: Backend,
 {
-    app.tabs.tabs[app.tabs.index].print_height = area.height - 1;
+    let ti = &mut app.tabs.tabs[app.tabs.index];
+    ti.print_height = if ti.rows > 0 {
+        ti.rows
+    } else {
+        let ruler = ti.ruler && ti.display == Display::Element;
+        area.height.saturating_sub(1 + ruler as u16).max(1)
+    };
+    ti.cursor_row = ti.cursor_row.min(ti.print_height - 1);
+    let mut area = area;
+    if app.tabs.tabs[app.tabs.index].split {
+        let width = area.width / 2;
+        let rect = Rect::new(area.x + width, area.y, area.width - width, area.height);
+        let data = app.on_draw_split();
+        let paragraph = Paragraph::new(data.to_vec()).wrap(Wrap { trim: true });
+        f.render_widget(paragraph, rect);
+        area.width = width;
+    }
     let data = app.on_draw();
 
     let paragraph = Paragraph::new(data.to_vec()).wrap(Wrap { trim: true });
     f.render_widget(paragraph, area);
 }
 
+fn draw_scrollbar<B>(f: &mut Frame<B>, app: &mut App, area: Rect)
+where
+    B: Backend,
+{
+    if let Some((start, length)) = app.scroll_position() {
+        let height = area.height as f64;
+        let top = ((start * height) as u16).min(area.height.saturating_sub(1));
+        let size = ((length * height).ceil() as u16).max(1);
+        let bar: Vec<Spans> = (0..area.height)
+            .map(|y| {
+                if y >= top && y < top + size {
+                    Spans::from(Span::styled("\u{2588}", app.theme.offset))
+                } else {
+                    Spans::from(Span::styled("\u{2502}", app.theme.null))
+                }
+            })
+            .collect();
+        f.render_widget(Paragraph::new(bar), area);
+    }
+}
+
 fn draw_help<B>(f: &mut Frame<B>, app: &mut App)
 where
     B: Backend,
@@ -71,7 +136,18 @@ where
     f.render_widget(paragraph, area);
 }
 
-fn draw_history<B>(f: &mut Frame<B>, _app: &mut App, print: &mut Print)
+fn draw_hit_groups<B>(f: &mut Frame<B>, app: &mut App)
+where
+    B: Backend,
+{
+    let area = Rect::new(0, 0, f.size().width, f.size().height - 1);
+    let paragraph = Paragraph::new(app.get_hit_groups())
+        .wrap(Wrap { trim: true })
+        .scroll((0, 0));
+    f.render_widget(paragraph, area);
+}
+
+fn draw_history<B>(f: &mut Frame<B>, app: &mut App, print: &mut Print)
 where
     B: Backend,
 {
@@ -86,7 +162,13 @@ where
         .take(area.height as usize)
         .rev()
     {
-        last_history.push(l.clone());
+        let mut line = l.clone();
+        if print.history.is_match(l) {
+            for span in line.0.iter_mut() {
+                span.style = span.style.patch(app.theme.current_text);
+            }
+        }
+        last_history.push(line);
     }
     let paragraph = Paragraph::new(last_history)
         .wrap(Wrap { trim: true })
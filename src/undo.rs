@@ -1,20 +1,143 @@
 use crate::data::Data;
+use std::collections::VecDeque;
 
-#[derive(Clone, Eq, PartialEq)]
+// one group per logical edit, so a fill or a multi-cursor edit is undone as a whole
+#[derive(Clone, Eq, PartialEq, Default)]
 pub struct UndoRedo {
-    e: Vec<Data>,
+    e: VecDeque<Vec<Data>>,
+    open: bool,
+    // bytes held by all groups and the most kept before the oldest groups are dropped, 0 keeps all
+    bytes: usize,
+    limit: usize,
 }
 
 impl UndoRedo {
-    pub fn new() -> UndoRedo {
-        UndoRedo { e: Vec::new() }
+    pub fn new(limit: usize) -> UndoRedo {
+        UndoRedo {
+            e: VecDeque::new(),
+            open: false,
+            bytes: 0,
+            limit,
+        }
     }
 
-    pub fn pop(&mut self) -> Option<Data> {
-        self.e.pop()
+    fn group_bytes(group: &[Data]) -> usize {
+        group.iter().map(|data| data.data.len()).sum()
+    }
+
+    // the newest group is always kept, even when it alone is over the limit
+    fn trim(&mut self) {
+        while self.limit > 0 && self.bytes > self.limit && self.e.len() > 1 {
+            if let Some(group) = self.e.pop_front() {
+                self.bytes -= Self::group_bytes(&group);
+            }
+        }
+    }
+
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+        self.trim();
+    }
+
+    // the next push starts a new group
+    pub fn begin(&mut self) {
+        self.open = false;
+    }
+
+    pub fn clear(&mut self) {
+        self.e.clear();
+        self.open = false;
+        self.bytes = 0;
+    }
+
+    pub fn pop(&mut self) -> Option<Vec<Data>> {
+        self.open = false;
+        let group = self.e.pop_back()?;
+        self.bytes -= Self::group_bytes(&group);
+        Some(group)
     }
 
     pub fn push(&mut self, data: Data) {
-        self.e.push(data);
+        self.bytes += data.data.len();
+        match self.e.back_mut() {
+            Some(group) if self.open => group.push(data),
+            _ => {
+                self.e.push_back(vec![data]);
+                self.open = true;
+            }
+        }
+        self.trim();
+    }
+
+    pub fn push_group(&mut self, group: Vec<Data>) {
+        self.open = false;
+        self.bytes += Self::group_bytes(&group);
+        self.e.push_back(group);
+        self.trim();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edit(undo: &mut UndoRedo, offset: u64, len: usize) {
+        undo.begin();
+        undo.push(Data::new(offset, vec![0; len]));
+        undo.push(Data::new(offset, vec![1; len]));
+    }
+
+    #[test]
+    fn pushes_join_the_open_group() {
+        let mut undo = UndoRedo::new(0);
+        edit(&mut undo, 0, 1);
+        undo.push(Data::new(16, vec![0]));
+        undo.push(Data::new(16, vec![1]));
+        edit(&mut undo, 32, 1);
+        let group = undo.pop().unwrap();
+        assert_eq!(group.len(), 2);
+        assert_eq!(group[0].offset, 32);
+        let group = undo.pop().unwrap();
+        let offsets: Vec<u64> = group.iter().map(|d| d.offset).collect();
+        assert_eq!(offsets, [0, 0, 16, 16]);
+        assert!(undo.pop().is_none());
+    }
+
+    #[test]
+    fn pop_closes_the_group() {
+        let mut undo = UndoRedo::new(0);
+        edit(&mut undo, 0, 1);
+        edit(&mut undo, 8, 1);
+        let group = undo.pop().unwrap();
+        undo.push_group(group);
+        undo.push(Data::new(16, vec![0]));
+        assert_eq!(undo.pop().unwrap().len(), 1);
+        assert_eq!(undo.pop().unwrap()[0].offset, 8);
+    }
+
+    #[test]
+    fn limit_drops_oldest_groups() {
+        let mut undo = UndoRedo::new(40);
+        edit(&mut undo, 0, 8);
+        edit(&mut undo, 8, 8);
+        edit(&mut undo, 16, 8);
+        assert_eq!(undo.pop().map(|g| g[0].offset), Some(16));
+        assert_eq!(undo.pop().map(|g| g[0].offset), Some(8));
+        assert!(undo.pop().is_none());
+    }
+
+    #[test]
+    fn limit_keeps_newest_group() {
+        let mut undo = UndoRedo::new(4);
+        edit(&mut undo, 0, 1);
+        edit(&mut undo, 8, 16);
+        assert_eq!(undo.pop().map(|g| g[0].offset), Some(8));
+        assert!(undo.pop().is_none());
+        edit(&mut undo, 0, 1);
+        edit(&mut undo, 8, 1);
+        undo.set_limit(0);
+        edit(&mut undo, 16, 16);
+        assert_eq!(undo.pop().map(|g| g[0].offset), Some(16));
+        assert_eq!(undo.pop().map(|g| g[0].offset), Some(8));
     }
 }